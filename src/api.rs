@@ -1,19 +1,64 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{net::{IpAddr, SocketAddr}, sync::Arc, time::{Duration, SystemTime}, collections::HashMap};
 
-use axum::{Router, extract::{Path, State, WebSocketUpgrade}, response::{IntoResponse, Response}, Json, routing::{get, post}, http::{Request, HeaderMap}, body::Body};
+use axum::{Router, extract::{Path, State, WebSocketUpgrade, Query, ConnectInfo}, response::{IntoResponse, Response}, Json, routing::{get, post, delete}, http::{Request, HeaderMap, HeaderValue, header::{ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, VARY}}, body::Body};
+use chrono::{NaiveDate, Utc, DateTime};
+use serde::Deserialize;
 use reqwest::StatusCode;
 use tokio::sync::{RwLock, broadcast::Sender};
 use tower::ServiceBuilder;
 use tower_http::{compression::CompressionLayer, trace::TraceLayer};
 use tracing::{log, Span};
 
-use crate::{SafeApiSeasonService, api_game_details::ApiGameDetailsService, api_season_service::ApiSeasonService, api_teams_service::{ApiTeamsService, ApiTeam}, standing_service::StandingService, models::{League, Season}, vote_service::{Vote, SafeVoteService}, api_ws::{ApiWs, WsMsg}, user_service::UserService, models_legacy::{game_details::LegacyGameDetails, player_stats::LegacyPlayerStats, season_games::LegacyGame}, api_player_stats_service::{ApiPlayerStatsService, TeamSeasonKey}, playoff_service::PlayoffService, CONFIG, models_api::{vote::{VoteBody, ApiVotePerGame}, game_details::ApiGameDetails, report::GameStatus, user::AddUser, live_activity::{StartLiveActivity, EndLiveActivity}, update_report::ApiUpdateReport}, status_service::StatusService, msg_bus::{MsgBus, Msg, UpdateReport}};
+use crate::{SafeApiSeasonService, api_game_details::ApiGameDetailsService, api_season_service::{ApiSeasonService, GamesFilter}, api_teams_service::{ApiTeamsService, ApiTeam, TeamsMap}, standing_service::StandingService, models::{League, Season}, vote_service::{Vote, VoteService, SafeVoteService}, api_ws::{ApiWs, WsMsg}, user_service::UserService, models_legacy::{game_details::LegacyGameDetails, player_stats::LegacyPlayerStats, season_games::LegacyGame}, api_player_stats_service::{ApiPlayerStatsService, TeamSeasonKey}, playoff_service::PlayoffService, odds_service::OddsService, pickem_service::PickemService, pickem_group_service::{PickemGroupService, SafePickemGroupService}, prediction_service::{PredictionService, SafePredictionService}, form_service::FormService, event_service::EventService, game_report_service::GameReportService, head_to_head_service::HeadToHeadService, calendar_service::CalendarService, feed_service::FeedService, export_service::{ExportService, ExportDataset, ExportFormat}, response_cache::CachedResponse, CONFIG, models_api::{game::ApiGame, vote::{VoteBody, ApiVotePerGame, VoteAnomalies, PurgeVotes}, game_details::ApiGameDetails, report::GameStatus, user::{AddUser, UserCalendar}, live_activity::{StartLiveActivity, EndLiveActivity}, update_report::ApiUpdateReport, standings::LiveStandings, sync::ApiSync, pickem::{CreatePickemGroup, PickemGroupMember, PredictionBody, Prediction}}, status_service::{StatusService, Status}, msg_bus::{MsgBus, Msg, UpdateReport}};
+
+const APPLICATION_JSON: &str = "application/json";
+const TEXT_PLAIN: &str = "text/plain; charset=utf-8";
+const TEXT_CALENDAR: &str = "text/calendar; charset=utf-8";
+const APPLICATION_ATOM: &str = "application/atom+xml; charset=utf-8";
+
+#[derive(Deserialize)]
+pub struct StatusQuery {
+    pub app_version: Option<String>,
+    pub league: Option<League>,
+}
+
+#[derive(Deserialize)]
+pub struct StandingsHistoryQuery {
+    pub date: Option<NaiveDate>,
+    pub round: Option<u16>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncQuery {
+    pub cursor: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub events: Option<bool>, // include events and reports of changed games
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Deserialize)]
+pub struct FormQuery {
+    pub team: Option<String>,
+    pub games: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct PickemLeaderboardQuery {
+    pub week: Option<String>,
+    pub limit: Option<usize>,
+}
 
 #[derive(Clone)]
 pub struct ApiState {
     pub game_details_service: ApiGameDetailsService,
     pub season_service: SafeApiSeasonService,
     pub vote_service: SafeVoteService,
+    pub prediction_service: SafePredictionService,
+    pub pickem_group_service: SafePickemGroupService,
     pub broadcast_sender: Sender<WsMsg>,
     pub msg_bus: Arc<MsgBus>,
     pub nr_ws: Arc<RwLock<i16>>,
@@ -21,11 +66,13 @@ pub struct ApiState {
 
 pub struct Api;
 impl Api {
-    pub async fn serve(port: u16, season_service: SafeApiSeasonService, vote_service: SafeVoteService, broadcast_sender: Sender<WsMsg>, msg_bus: Arc<MsgBus>) {
+    pub async fn serve(port: u16, season_service: SafeApiSeasonService, vote_service: SafeVoteService, prediction_service: SafePredictionService, broadcast_sender: Sender<WsMsg>, msg_bus: Arc<MsgBus>) {
         let state = ApiState {
             game_details_service: ApiGameDetailsService::new(season_service.clone()),
             season_service,
             vote_service,
+            prediction_service,
+            pickem_group_service: PickemGroupService::new(),
             broadcast_sender,
             msg_bus,
             nr_ws: Arc::new(RwLock::new(0)),
@@ -42,11 +89,33 @@ impl Api {
             .route("/user", post(Api::add_user))
 
             .route("/v2/games/:season", get(Api::get_games))
+            .route("/v2/sync", get(Api::get_sync))
             .route("/v2/game/:game_uuid", get(Api::get_game_details))
             .route("/v2/game/updated/:game_uuid", get(Api::get_updated_game_details))
             .route("/v2/teams", get(Api::get_teams))
+            .route("/v2/standings/live", get(Api::get_live_standings))
             .route("/v2/standings/:season", get(Api::get_leagues))
+            .route("/v2/standings/:season/history", get(Api::get_standings_history))
+            .route("/v2/standings/:season/trajectory", get(Api::get_standings_trajectory))
             .route("/v2/playoffs/:season", get(Api::get_playoffs))
+            .route("/v2/odds/:season", get(Api::get_odds))
+            .route("/v2/form/:season", get(Api::get_form))
+            .route("/v2/h2h/:team1/:team2", get(Api::get_head_to_head))
+            .route("/v2/calendar/team/:team", get(Api::get_team_calendar))
+            .route("/v2/calendar/league/:league", get(Api::get_league_calendar))
+            .route("/v2/calendar/user/:user_id/:token", get(Api::get_user_calendar))
+            .route("/v2/feed/team/:team", get(Api::get_team_feed))
+            .route("/v2/feed/league/:league", get(Api::get_league_feed))
+            .route("/v2/export/:season/archive", get(Api::get_export_archive))
+            .route("/v2/export/:season/:dataset", get(Api::get_export))
+            .route("/v2/pickem/:season/leaderboard", get(Api::get_pickem_leaderboard))
+            .route("/v2/pickem/:season/user/:user_id", get(Api::get_pickem_user))
+            .route("/v2/pickem/:season/group/:invite_code/leaderboard", get(Api::get_pickem_group_leaderboard))
+            .route("/v2/pickem/group", post(Api::create_pickem_group))
+            .route("/v2/pickem/group/join", post(Api::join_pickem_group))
+            .route("/v2/pickem/group/leave", post(Api::leave_pickem_group))
+            .route("/v2/pickem/group/:invite_code/votes", get(Api::get_pickem_group_votes))
+            .route("/v2/pickem/groups/:user_id", get(Api::get_pickem_groups))
             .route("/v2/player/:player_id", get(Api::get_player))
             .route("/v2/players/:season", get(Api::get_season_players))
             .route("/v2/players/:season/:team", get(Api::get_players))
@@ -54,13 +123,20 @@ impl Api {
             .route("/v2/live-activity/start", post(Api::start_live_activity))
             .route("/v2/live-activity/end", post(Api::end_live_activity))
             .route("/v2/user", post(Api::add_user))
+            .route("/v2/user/:user_id/calendar", post(Api::create_user_calendar))
 
             .route("/v2/vote", post(Api::vote))
+            .route("/v2/prediction", post(Api::predict))
+            .route("/v2/predictions/:game_uuid/:user_id", get(Api::get_predictions))
 
 
             .route("/v2/ws", get(Api::ws_handler))
 
-            .route("/v2/status", get(Api::get_status))
+            .route("/v2/status", get(Api::get_status).post(Api::upsert_status).delete(Api::clear_status))
+            .route("/v2/status/all", get(Api::get_all_status))
+            .route("/v2/status/:id", delete(Api::remove_status))
+            .route("/v2/votes/anomalies", get(Api::get_vote_anomalies))
+            .route("/v2/votes/purge", post(Api::purge_votes))
             .route("/v2/update-report", post(Api::update_report))
     
             .route("/", get(Api::root))
@@ -83,7 +159,7 @@ impl Api {
         let addr = SocketAddr::from(([0, 0, 0, 0], port));
         log::info!("[API] Listening on {}", addr);
         _ = axum::Server::bind(&addr)
-            .serve(app.into_make_service())
+            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
             .await;
     }
 
@@ -104,31 +180,72 @@ impl Api {
         }
     }
 
-    async fn get_games(Path(season): Path<String>) -> impl IntoResponse {
+    async fn get_games(Path(season): Path<String>, Query(filter): Query<GamesFilter>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
         if let Ok(season) = season.parse() {
-            (StatusCode::OK, ApiSeasonService::read_raw(&season))
+            let games = state.season_service.read().await.read_season(&season);
+            let body = serde_json::to_string(&filter.apply(games, Utc::now())).unwrap_or_default();
+            let cached = CachedResponse::new(body, ApiSeasonService::last_modified(&season));
+            (StatusCode::OK, Api::conditional(&headers, &cached, APPLICATION_JSON, Api::get_cache_control(&season, 10)))
         } else {
-            (StatusCode::NOT_FOUND, "404".to_string())
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
     }
     
+    async fn get_sync(Query(query): Query<SyncQuery>, State(state): State<ApiState>) -> Json<ApiSync> {
+        let (cursor, games) = state.season_service.read().await.read_changes(query.cursor.as_deref(), query.since);
+        let full_sync = games.is_none();
+        let games = games.unwrap_or_default();
+        let (mut events, mut reports) = (HashMap::new(), HashMap::new());
+        if query.events.unwrap_or(false) {
+            for game in games.iter().filter(|e| e.status != GameStatus::Coming) {
+                events.insert(game.game_uuid.clone(), EventService::read(&game.game_uuid));
+                if let Some(report) = GameReportService::read(&game.game_uuid) {
+                    reports.insert(game.game_uuid.clone(), report);
+                }
+            }
+        }
+        Json(ApiSync { cursor, full_sync, games, events, reports })
+    }
+
     async fn get_game_details(Path(game_uuid): Path<String>, State(state): State<ApiState>) -> Json<Option<ApiGameDetails>> {
         Json(state.game_details_service.read(&game_uuid, None).await)
     }
     
-    async fn get_teams() -> impl IntoResponse {
-        ApiTeamsService::read_raw()
+    async fn get_teams(headers: HeaderMap) -> impl IntoResponse {
+        Api::conditional(&headers, &ApiTeamsService::read_cached(), TEXT_PLAIN, "public, max-age=3600".to_string())
     }
 
     async fn get_legacy_teams() -> impl IntoResponse {
         Json(ApiTeamsService::read().into_iter().filter(|e| e.league == Some(League::SHL)).collect::<Vec<ApiTeam>>())
     }
     
-    async fn get_leagues(season: Option<Path<String>>) -> impl IntoResponse {
+    async fn get_leagues(season: Option<Path<String>>, headers: HeaderMap) -> impl IntoResponse {
         if let Ok(season) = season.map(|e| e.parse()).unwrap_or_else(|| Ok(Season::get_current())) {
-            (StatusCode::OK, StandingService::read_raw(season))
+            let cache_control = Api::get_cache_control(&season, 60);
+            (StatusCode::OK, Api::conditional(&headers, &StandingService::read_cached(season), TEXT_PLAIN, cache_control))
+        } else {
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        }
+    }
+
+    async fn get_live_standings(State(state): State<ApiState>) -> Json<LiveStandings> {
+        let games = state.season_service.read().await.read_current_season();
+        Json(StandingService::get_live(&games))
+    }
+
+    async fn get_standings_history(Path(season): Path<String>, Query(query): Query<StandingsHistoryQuery>) -> impl IntoResponse {
+        if let Ok(season) = season.parse() {
+            (StatusCode::OK, Json(StandingService::read_as_of(&season, query.date, query.round)).into_response())
         } else {
-            (StatusCode::NOT_FOUND, "404".to_string())
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        }
+    }
+
+    async fn get_standings_trajectory(Path(season): Path<String>) -> impl IntoResponse {
+        if let Ok(season) = season.parse() {
+            (StatusCode::OK, Json(StandingService::read_trajectory(&season)).into_response())
+        } else {
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
     }
 
@@ -142,7 +259,7 @@ impl Api {
     async fn get_legacy_standings(season: Option<Path<String>>) -> impl IntoResponse {
         if let Ok(season) = season.map(|e| e.parse()).unwrap_or_else(|| Ok(Season::get_current())) {
             let standings = StandingService::read(season);
-            (StatusCode::OK, Json(standings.map(|e| e.SHL).unwrap_or_default()).into_response())
+            (StatusCode::OK, Json(standings.map(|e| e.get(&League::SHL).to_vec()).unwrap_or_default()).into_response())
         } else {
             (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
@@ -150,7 +267,7 @@ impl Api {
     
     async fn get_legacy_players(Path(team): Path<String>) -> impl IntoResponse {
         let db = ApiPlayerStatsService::get_team_player_db();
-        let data: Vec<LegacyPlayerStats> = db.read(&TeamSeasonKey(Season::Season2022, team))
+        let data: Vec<LegacyPlayerStats> = db.read(&TeamSeasonKey(Season(2022), team))
             .unwrap_or_default()
             .into_iter()
             .map(|e| e.into())
@@ -158,40 +275,270 @@ impl Api {
         Json(data)
     }
 
-    async fn get_players(Path((season, team)): Path<(String, String)>) -> impl IntoResponse {
+    async fn get_players(Path((season, team)): Path<(String, String)>, headers: HeaderMap) -> impl IntoResponse {
         if let Ok(e) = season.parse() {
-            let db = ApiPlayerStatsService::get_team_player_db();
-            (StatusCode::OK, db.read_raw(&TeamSeasonKey(e, team)))
+            let cache_control = Api::get_cache_control(&e, 300);
+            (StatusCode::OK, Api::conditional(&headers, &ApiPlayerStatsService::read_team_cached(&TeamSeasonKey(e, team)), TEXT_PLAIN, cache_control))
         } else {
-            (StatusCode::NOT_FOUND, "404".to_string())
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
     }
 
-    async fn get_player(Path(player_id): Path<i32>) -> impl IntoResponse {
-        let db = ApiPlayerStatsService::get_player_career_db();
-        db.read_raw(&player_id)
+    async fn get_player(Path(player_id): Path<i32>, headers: HeaderMap) -> impl IntoResponse {
+        Api::conditional(&headers, &ApiPlayerStatsService::read_career_cached(player_id), TEXT_PLAIN, "public, max-age=300".to_string())
     } 
 
-    async fn get_season_players(Path(season): Path<String>) -> impl IntoResponse {
+    async fn get_season_players(Path(season): Path<String>, headers: HeaderMap) -> impl IntoResponse {
         if let Ok(e) = season.parse() {
-            (StatusCode::OK, ApiPlayerStatsService::get_season_player_db().read_raw(&e).into_response())
+            (StatusCode::OK, Api::conditional(&headers, &ApiPlayerStatsService::read_season_cached(&e), TEXT_PLAIN, Api::get_cache_control(&e, 300)))
         } else {
             (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
     } 
 
-    async fn get_playoffs(Path(season): Path<String>) -> impl IntoResponse {
+    async fn get_playoffs(Path(season): Path<String>, headers: HeaderMap) -> impl IntoResponse {
+        if let Ok(e) = season.parse() {
+            (StatusCode::OK, Api::conditional(&headers, &PlayoffService::read_cached(&e), TEXT_PLAIN, Api::get_cache_control(&e, 60)))
+        } else {
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        }
+    }
+
+    async fn get_odds(Path(season): Path<String>) -> impl IntoResponse {
         if let Ok(e) = season.parse() {
-            (StatusCode::OK, PlayoffService::get_db().read_raw(&e).into_response())
+            (StatusCode::OK, OddsService::read_raw(&e).into_response())
+        } else {
+            (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        }
+    }
+
+    async fn get_form(Path(season): Path<String>, Query(query): Query<FormQuery>, State(state): State<ApiState>) -> impl IntoResponse {
+        let Ok(season) = season.parse::<Season>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = match season.is_current() {
+            true => state.season_service.read().await.read_current_season(),
+            false => ApiSeasonService::read(&season),
+        };
+        let form: Vec<_> = FormService::get(&games, query.games.unwrap_or(5)).into_iter()
+            .filter(|e| query.team.as_ref().map(|team| &e.team_code == team).unwrap_or(true))
+            .collect();
+        (StatusCode::OK, Json(form).into_response())
+    }
+
+    async fn get_team_calendar(Path(team): Path<String>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        let team = team.trim_end_matches(".ics").to_string();
+        let teams = ApiTeamsService::read();
+        let Some(name) = teams.iter().find(|e| e.code == team).map(|e| e.name.clone()) else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = state.season_service.read().await.read_current_season();
+        let games: Vec<ApiGame> = games.into_iter().filter(|e| e.home_team_code == team || e.away_team_code == team).collect();
+        (StatusCode::OK, Api::calendar(&headers, &name, &games, &teams))
+    }
+
+    async fn get_league_calendar(Path(league): Path<String>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        let Ok(league) = league.trim_end_matches(".ics").parse::<League>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = state.season_service.read().await.read_current_season();
+        let games: Vec<ApiGame> = games.into_iter().filter(|e| e.league == league).collect();
+        (StatusCode::OK, Api::calendar(&headers, league.as_str(), &games, &ApiTeamsService::read()))
+    }
+
+    async fn get_user_calendar(Path((user_id, token)): Path<(String, String)>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        // calendar apps can't send the api key, the token in the url is the secret
+        let Some(user) = UserService::read_by_calendar_token(&user_id, token.trim_end_matches(".ics")) else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = state.season_service.read().await.read_current_season();
+        let games: Vec<ApiGame> = games.into_iter().filter(|e| user.teams.contains(&e.home_team_code) || user.teams.contains(&e.away_team_code)).collect();
+        (StatusCode::OK, Api::calendar(&headers, "Mina lag", &games, &ApiTeamsService::read()))
+    }
+
+    fn calendar(headers: &HeaderMap, name: &str, games: &[ApiGame], teams: &[ApiTeam]) -> Response {
+        let team_names = teams.iter().map(|e| (e.code.clone(), e.name.clone())).collect();
+        let last_modified = ApiSeasonService::last_modified(&Season::get_current());
+        // DTSTAMP changes on every build, keep it stable between schedule changes so the ETag holds
+        let now = last_modified.map(DateTime::<Utc>::from).unwrap_or_else(Utc::now);
+        let cached = CachedResponse::new(CalendarService::get(name, games, &team_names, now), last_modified);
+        Api::conditional(headers, &cached, TEXT_CALENDAR, "public, max-age=900".to_string())
+    }
+
+    async fn get_team_feed(Path(team): Path<String>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        let team = team.trim_end_matches(".atom").to_string();
+        let teams = TeamsMap::new();
+        let Some(name) = teams.get(&team).map(|e| e.name.clone()) else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = state.season_service.read().await.read_current_season();
+        let games: Vec<ApiGame> = games.into_iter().filter(|e| e.home_team_code == team || e.away_team_code == team).collect();
+        (StatusCode::OK, Api::feed(&headers, &name, &format!("team:{team}"), games, &teams, &[team]))
+    }
+
+    async fn get_league_feed(Path(league): Path<String>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        let Ok(league) = league.trim_end_matches(".atom").parse::<League>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = state.season_service.read().await.read_current_season();
+        let games: Vec<ApiGame> = games.into_iter().filter(|e| e.league == league).collect();
+        (StatusCode::OK, Api::feed(&headers, league.as_str(), &format!("league:{league}"), games, &TeamsMap::new(), &[]))
+    }
+
+    fn feed(headers: &HeaderMap, title: &str, feed_id: &str, games: Vec<ApiGame>, teams: &TeamsMap, user_teams: &[String]) -> Response {
+        let cached = FeedService::read_cached(title, feed_id, games, teams, user_teams);
+        Api::conditional(headers, &cached, APPLICATION_ATOM, "public, max-age=60".to_string())
+    }
+
+    async fn get_export(Path((season, dataset)): Path<(String, ExportDataset)>, Query(query): Query<ExportQuery>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        if !Api::is_admin(&headers) && !Api::has_api_key(&headers) {
+            return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        }
+        let Ok(season) = season.parse::<Season>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let format = query.format.unwrap_or_default();
+        let games = state.season_service.read().await.read_season(&season);
+        // reads every game's files from disk
+        let export = {
+            let season = season.clone();
+            tokio::task::spawn_blocking(move || ExportService::export(dataset, format, &season, &games)).await
+        };
+        let Ok(data) = export else {
+            log::error!("[API] Export {season} {} failed", dataset.as_str());
+            return (StatusCode::INTERNAL_SERVER_ERROR, "500".to_string().into_response())
+        };
+        let disposition = format!("attachment; filename=\"{}_{}.{}\"", season.0, dataset.as_str(), format.get_extension());
+        (StatusCode::OK, ([(CONTENT_TYPE, format.get_content_type().to_string()), (CONTENT_DISPOSITION, disposition)], data).into_response())
+    }
+
+    async fn get_export_archive(Path(season): Path<String>, Query(query): Query<ExportQuery>, State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+        if !Api::is_admin(&headers) && !Api::has_api_key(&headers) {
+            return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        }
+        let Ok(season) = season.parse::<Season>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let format = query.format.unwrap_or_default();
+        let games = state.season_service.read().await.read_season(&season);
+        let archive = {
+            let season = season.clone();
+            tokio::task::spawn_blocking(move || ExportService::archive(format, &season, &games)).await
+                .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+        };
+        match archive {
+            Ok(data) => {
+                let disposition = format!("attachment; filename=\"{}_{}.tar.gz\"", season.0, format.get_extension());
+                (StatusCode::OK, ([(CONTENT_TYPE, "application/gzip".to_string()), (CONTENT_DISPOSITION, disposition)], data).into_response())
+            },
+            Err(e) => {
+                log::error!("[API] Export archive failed {e}");
+                (StatusCode::INTERNAL_SERVER_ERROR, "500".to_string().into_response())
+            },
+        }
+    }
+
+    async fn get_head_to_head(Path((team1, team2)): Path<(String, String)>, headers: HeaderMap) -> impl IntoResponse {
+        // only known teams, every pair asked for is cached
+        let teams = TeamsMap::new();
+        if teams.get(&team1).is_none() || teams.get(&team2).is_none() {
+            return (StatusCode::NOT_FOUND, "404".to_string()).into_response()
+        }
+        Api::conditional(&headers, &HeadToHeadService::read_cached(&team1, &team2), APPLICATION_JSON, "public, max-age=60".to_string())
+    }
+
+    async fn get_pickem_leaderboard(Path(season): Path<String>, Query(query): Query<PickemLeaderboardQuery>) -> impl IntoResponse {
+        if let Ok(season) = season.parse() {
+            let week = match query.week.as_deref() {
+                Some("current") => Some(PickemService::get_week(&Utc::now())),
+                week => week.map(|e| e.to_string()),
+            };
+            let leaderboard = PickemService::read_leaderboard(&season, week.as_deref(), query.limit.unwrap_or(100));
+            (StatusCode::OK, Json(leaderboard).into_response())
         } else {
             (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
     }
 
+    async fn get_pickem_user(Path((season, user_id)): Path<(String, String)>, State(state): State<ApiState>) -> impl IntoResponse {
+        let Ok(season) = season.parse::<Season>() else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let games = match season.is_current() {
+            true => state.season_service.read().await.read_current_season(),
+            false => ApiSeasonService::read(&season),
+        };
+        let history = PickemService::read_user_history(&season, &user_id, &games, &VoteService::read_votes());
+        (StatusCode::OK, Json(history).into_response())
+    }
+
+    async fn get_pickem_group_leaderboard(Path((season, invite_code)): Path<(String, String)>, Query(query): Query<PickemLeaderboardQuery>) -> impl IntoResponse {
+        let (Ok(season), Some(group)) = (season.parse(), PickemGroupService::read(&invite_code)) else {
+            return (StatusCode::NOT_FOUND, "404".to_string().into_response())
+        };
+        let week = match query.week.as_deref() {
+            Some("current") => Some(PickemService::get_week(&Utc::now())),
+            week => week.map(|e| e.to_string()),
+        };
+        let leaderboard = PickemService::read_group_leaderboard(&season, week.as_deref(), &group.members);
+        (StatusCode::OK, Json(leaderboard).into_response())
+    }
+
+    async fn get_pickem_group_votes(Path(invite_code): Path<String>) -> impl IntoResponse {
+        match PickemGroupService::read(&invite_code) {
+            Some(group) => (StatusCode::OK, Json(VoteService::read_per_game_for(&group.members)).into_response()),
+            None => (StatusCode::NOT_FOUND, "404".to_string().into_response()),
+        }
+    }
+
+    async fn get_pickem_groups(Path(user_id): Path<String>) -> impl IntoResponse {
+        Json(PickemGroupService::read_for_user(&user_id))
+    }
+
+    async fn create_pickem_group(State(state): State<ApiState>, headers: HeaderMap, Json(req): Json<CreatePickemGroup>) -> impl IntoResponse {
+        if !Api::has_api_key(&headers) {
+            (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        } else if !UserService::exists(&req.user_id) {
+            (StatusCode::FORBIDDEN, "Unknown user".to_string().into_response())
+        } else if req.name.trim().is_empty() {
+            (StatusCode::BAD_REQUEST, "Invalid name".to_string().into_response())
+        } else {
+            (StatusCode::OK, Json(state.pickem_group_service.write().await.create(&req.user_id, &req.name)).into_response())
+        }
+    }
+
+    async fn join_pickem_group(State(state): State<ApiState>, headers: HeaderMap, Json(req): Json<PickemGroupMember>) -> impl IntoResponse {
+        if !Api::has_api_key(&headers) {
+            return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        }
+        if !UserService::exists(&req.user_id) {
+            return (StatusCode::FORBIDDEN, "Unknown user".to_string().into_response())
+        }
+        let joined = state.pickem_group_service.write().await.join(&req.invite_code, &req.user_id);
+        match joined {
+            Some(group) => (StatusCode::OK, Json(group).into_response()),
+            None => (StatusCode::NOT_FOUND, "Invalid invite code".to_string().into_response()),
+        }
+    }
+
+    async fn leave_pickem_group(State(state): State<ApiState>, headers: HeaderMap, Json(req): Json<PickemGroupMember>) -> impl IntoResponse {
+        if !Api::has_api_key(&headers) {
+            return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        }
+        if !UserService::exists(&req.user_id) {
+            return (StatusCode::FORBIDDEN, "Unknown user".to_string().into_response())
+        }
+        let left = state.pickem_group_service.write().await.leave(&req.invite_code, &req.user_id);
+        match left {
+            Some(group) => (StatusCode::OK, Json(group).into_response()),
+            None => (StatusCode::NOT_FOUND, "Invalid invite code".to_string().into_response()),
+        }
+    }
+
     async fn get_legacy_playoffs(Path(season): Path<String>) -> impl IntoResponse {
         if let Ok(e) = season.parse() {
             let playoffs = PlayoffService::get_db().read(&e);
-            (StatusCode::OK, Json(playoffs.map(|e| e.SHL)).into_response())
+            (StatusCode::OK, Json(playoffs.and_then(|mut e| e.0.remove(&League::SHL))).into_response())
         } else {
             (StatusCode::NOT_FOUND, "404".to_string().into_response())
         }
@@ -207,41 +554,276 @@ impl Api {
 
     async fn vote(
         headers: HeaderMap,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
         State(state): State<ApiState>, 
         Json(vote): Json<VoteBody>
     ) -> Result<Json<ApiVotePerGame>, (StatusCode, String)> {
-        let key = headers.get("x-api-key").and_then(|e| e.to_str().ok()).unwrap_or_default();
-        if key != CONFIG.api_key {
-            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
-        } else if let Some(game) = state.season_service.read().await.read_current_season_game(&vote.game_uuid) {
-            if game.home_team_code != vote.team_code && game.away_team_code != vote.team_code {
-                Err((StatusCode::BAD_REQUEST, "Invalid team_code".to_string()))
-            } else if game.status != GameStatus::Coming {
-                Err((StatusCode::BAD_REQUEST, "Invalid game status".to_string()))
-            } else {
-                let is_home_winner = game.home_team_code == vote.team_code;
-                let vote = Vote { user_id: vote.user_id, game_uuid: vote.game_uuid, team_code: vote.team_code, is_home_winner };
-                let mut vs = state.vote_service.write().await;
-                Ok(Json(vs.vote(vote).await.into()))
-            }
-        } else {
-            Err((StatusCode::BAD_REQUEST, "Invalid game".to_string()))
+        if !Api::has_api_key(&headers) {
+            return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        }
+        if !UserService::exists(&vote.user_id) {
+            return Err((StatusCode::FORBIDDEN, "Unknown user".to_string()))
+        }
+        let Some(game) = state.season_service.read().await.read_current_season_game(&vote.game_uuid) else {
+            return Err((StatusCode::BAD_REQUEST, "Invalid game".to_string()))
+        };
+        if game.home_team_code != vote.team_code && game.away_team_code != vote.team_code {
+            return Err((StatusCode::BAD_REQUEST, "Invalid team_code".to_string()))
+        }
+        if game.status != GameStatus::Coming {
+            return Err((StatusCode::BAD_REQUEST, "Invalid game status".to_string()))
+        }
+        // only valid votes count towards the limit
+        let mut vs = state.vote_service.write().await;
+        if vs.is_rate_limited(&vote.user_id, Some(&Api::get_client_ip(&headers, &addr))) {
+            return Err((StatusCode::TOO_MANY_REQUESTS, "Too many votes".to_string()))
         }
+        let is_home_winner = game.home_team_code == vote.team_code;
+        let vote = Vote { user_id: vote.user_id, game_uuid: vote.game_uuid, team_code: vote.team_code, is_home_winner, created: Some(Utc::now()) };
+        Ok(Json(vs.vote(vote).await.into()))
     }  
 
+    async fn predict(
+        headers: HeaderMap,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        State(state): State<ApiState>,
+        Json(req): Json<PredictionBody>
+    ) -> Result<Json<Prediction>, (StatusCode, String)> {
+        if !Api::has_api_key(&headers) {
+            return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        }
+        if !UserService::exists(&req.user_id) {
+            return Err((StatusCode::FORBIDDEN, "Unknown user".to_string()))
+        }
+        let Some(game) = state.season_service.read().await.read_current_season_game(&req.game_uuid) else {
+            return Err((StatusCode::BAD_REQUEST, "Invalid game".to_string()))
+        };
+        if game.status != GameStatus::Coming {
+            return Err((StatusCode::BAD_REQUEST, "Invalid game status".to_string()))
+        }
+        let kind = PredictionService::validate(&game, req.kind)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        // only valid predictions count towards the limit
+        if state.vote_service.write().await.is_rate_limited(&req.user_id, Some(&Api::get_client_ip(&headers, &addr))) {
+            return Err((StatusCode::TOO_MANY_REQUESTS, "Too many votes".to_string()))
+        }
+        Ok(Json(state.prediction_service.write().await.predict(&game, &req.user_id, kind)))
+    }
+
+    async fn get_predictions(Path((game_uuid, user_id)): Path<(String, String)>) -> Json<Vec<Prediction>> {
+        Json(PredictionService::read(&game_uuid).into_iter().filter(|e| e.user_id == user_id).collect())
+    }
+
     async fn add_user(Json(user): Json<AddUser>) -> impl IntoResponse {
         UserService::handle(user);
         (StatusCode::OK, "success".to_string())
     }
 
+    async fn create_user_calendar(Path(user_id): Path<String>, headers: HeaderMap) -> impl IntoResponse {
+        if !Api::has_api_key(&headers) {
+            return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string().into_response())
+        }
+        match UserService::get_calendar_token(&user_id) {
+            Some(token) => (StatusCode::OK, Json(UserCalendar { path: format!("/v2/calendar/user/{user_id}/{token}.ics") }).into_response()),
+            None => (StatusCode::FORBIDDEN, "Unknown user".to_string().into_response()),
+        }
+    }
+
     async fn ws_handler(
         ws: WebSocketUpgrade,
         State(state): State<ApiState>) -> impl IntoResponse {
         ws.on_upgrade(|socket| ApiWs::handle(socket, state))
     } 
 
-    async fn get_status() -> impl IntoResponse {
-        StatusService::read_raw().into_response()
+    async fn get_status(Query(query): Query<StatusQuery>) -> impl IntoResponse {
+        match StatusService::read_current(query.app_version.as_deref(), query.league.as_ref()) {
+            Some(status) => Json(status).into_response(),
+            None => "".into_response(),
+        }
+    }
+
+    async fn get_vote_anomalies(headers: HeaderMap, State(state): State<ApiState>) -> Result<Json<VoteAnomalies>, (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        } else {
+            Ok(Json(state.vote_service.read().await.find_anomalies(UserService::exists)))
+        }
+    }
+
+    async fn purge_votes(
+        headers: HeaderMap,
+        State(state): State<ApiState>,
+        Json(req): Json<PurgeVotes>) -> Result<(StatusCode, String), (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        }
+        let mut vs = state.vote_service.write().await;
+        let user_ids = req.user_ids.unwrap_or_else(|| {
+            let anomalies = vs.find_anomalies(UserService::exists);
+            [anomalies.unknown_users, anomalies.burst_users].concat()
+        });
+        let nr_purged = vs.purge(&user_ids).await;
+        Ok((StatusCode::OK, format!("Purged {nr_purged} votes")))
+    }
+
+    async fn get_all_status(headers: HeaderMap) -> Result<Json<Vec<Status>>, (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        } else {
+            Ok(Json(StatusService::read_all()))
+        }
+    }
+
+    async fn upsert_status(
+        headers: HeaderMap,
+        State(state): State<ApiState>,
+        Json(status): Json<Status>) -> Result<Json<Status>, (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        } else {
+            let status = StatusService::upsert(status);
+            Api::broadcast_status(&state);
+            Ok(Json(status))
+        }
+    }
+
+    async fn remove_status(
+        headers: HeaderMap,
+        State(state): State<ApiState>,
+        Path(id): Path<String>) -> Result<(StatusCode, String), (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        } else if StatusService::remove(&id) {
+            Api::broadcast_status(&state);
+            Ok((StatusCode::OK, "success".to_string()))
+        } else {
+            Err((StatusCode::NOT_FOUND, "Not found".to_string()))
+        }
+    }
+
+    async fn clear_status(
+        headers: HeaderMap,
+        State(state): State<ApiState>) -> Result<(StatusCode, String), (StatusCode, String)> {
+        if !Api::is_admin(&headers) {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()))
+        } else {
+            StatusService::clear();
+            Api::broadcast_status(&state);
+            Ok((StatusCode::OK, "success".to_string()))
+        }
+    }
+
+    fn broadcast_status(state: &ApiState) {
+        _ = state.broadcast_sender.send(StatusService::read_active(None, None).into());
+    }
+
+    /**
+     * Past seasons never change, the current one is only cached for max_age seconds
+     */
+    fn get_cache_control(season: &Season, max_age: u32) -> String {
+        if season.is_current() {
+            format!("public, max-age={}", max_age)
+        } else {
+            "public, max-age=31536000, immutable".to_string()
+        }
+    }
+
+    /**
+     * Strong ETag per representation, 304 if the client already has it
+     */
+    fn conditional(headers: &HeaderMap, cached: &CachedResponse, content_type: &'static str, cache_control: String) -> Response {
+        // nothing stored yet, don't let it stick as immutable
+        let cache_control = if cached.body.is_empty() { "no-cache".to_string() } else { cache_control };
+        let accepts_gzip = headers.get(ACCEPT_ENCODING)
+            .and_then(|e| e.to_str().ok())
+            .map(|e| e.split(',').any(|e| e.trim().starts_with("gzip")))
+            .unwrap_or(false);
+        let (body, etag, encoding) = match &cached.gzip {
+            Some((body, etag)) if accepts_gzip => (body.clone(), etag, Some("gzip")),
+            _ => (cached.body.clone(), &cached.etag, None),
+        };
+        // If-Modified-Since only counts without If-None-Match
+        let not_modified = match headers.get(IF_NONE_MATCH) {
+            Some(e) => e.to_str().ok()
+                .map(|e| e.split(',').map(|e| e.trim().trim_start_matches("W/")).any(|e| e == etag || e == "*"))
+                .unwrap_or(false),
+            None => Api::is_unmodified_since(headers, cached.last_modified),
+        };
+
+        let mut response = if not_modified {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            ([(CONTENT_TYPE, content_type)], body).into_response()
+        };
+        let response_headers = response.headers_mut();
+        if let Ok(e) = HeaderValue::from_str(etag) {
+            response_headers.insert(ETAG, e);
+        }
+        if let Ok(e) = HeaderValue::from_str(&cache_control) {
+            response_headers.insert(CACHE_CONTROL, e);
+        }
+        if let Some(e) = cached.last_modified.and_then(|e| HeaderValue::from_str(&Api::format_http_date(e)).ok()) {
+            response_headers.insert(LAST_MODIFIED, e);
+        }
+        if cached.gzip.is_some() {
+            response_headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+        }
+        if let (Some(e), false) = (encoding, not_modified) {
+            // already compressed, the compression layer leaves it alone
+            response_headers.insert(CONTENT_ENCODING, HeaderValue::from_static(e));
+        }
+        response
+    }
+
+    /**
+     * Last-Modified is sent with second precision, so equal seconds are not modified
+     */
+    fn is_unmodified_since(headers: &HeaderMap, last_modified: Option<SystemTime>) -> bool {
+        let since = headers.get(IF_MODIFIED_SINCE)
+            .and_then(|e| e.to_str().ok())
+            .and_then(|e| DateTime::parse_from_rfc2822(e).ok());
+        match (since, last_modified) {
+            (Some(since), Some(modified)) => DateTime::<Utc>::from(modified).timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+
+    fn format_http_date(time: SystemTime) -> String {
+        DateTime::<Utc>::from(time).format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    }
+
+    fn is_admin(headers: &HeaderMap) -> bool {
+        let key = headers.get("x-admin-key").and_then(|e| e.to_str().ok()).unwrap_or_default();
+        key == CONFIG.api_admin_key
+    }
+
+    fn get_client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+        Api::get_client_ip_behind(headers, addr, &CONFIG.trusted_proxies)
+    }
+
+    /**
+     * The peer, unless it is a trusted proxy. Then the right-most hop of x-forwarded-for that is not one of ours,
+     * hops further left are set by the client and can not be believed
+     */
+    fn get_client_ip_behind(headers: &HeaderMap, addr: &SocketAddr, trusted_proxies: &[IpAddr]) -> String {
+        if !trusted_proxies.contains(&addr.ip()) {
+            return addr.ip().to_string()
+        }
+        let hops: Vec<&str> = headers.get_all("x-forwarded-for").iter()
+            .filter_map(|e| e.to_str().ok())
+            .flat_map(|e| e.split(','))
+            .map(|e| e.trim())
+            .collect();
+        hops.into_iter()
+            .rev()
+            .find(|e| !e.parse::<IpAddr>().map(|ip| trusted_proxies.contains(&ip)).unwrap_or(false))
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| addr.ip().to_string())
+    }
+
+    fn has_api_key(headers: &HeaderMap) -> bool {
+        let key = headers.get("x-api-key").and_then(|e| e.to_str().ok()).unwrap_or_default();
+        key == CONFIG.api_key
     }
 
     async fn update_report(
@@ -285,4 +867,101 @@ impl Api {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::{net::{IpAddr, SocketAddr}, time::{Duration, SystemTime}};
+
+    use axum::{http::{HeaderMap, HeaderName, header::{ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED}}, response::Response};
+    use reqwest::StatusCode;
+
+    use crate::response_cache::CachedResponse;
+
+    use super::{Api, TEXT_PLAIN};
+
+    #[test]
+    fn client_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let addr = SocketAddr::new(proxy, 443);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "6.6.6.6, 1.2.3.4, 10.0.0.2".parse().unwrap());
+
+        // untrusted peers can not pick their address
+        let direct = SocketAddr::new("5.5.5.5".parse().unwrap(), 443);
+        assert_eq!(Api::get_client_ip_behind(&headers, &direct, &[proxy]), "5.5.5.5");
+        assert_eq!(Api::get_client_ip_behind(&headers, &addr, &[]), "10.0.0.1");
+
+        assert_eq!(Api::get_client_ip_behind(&headers, &addr, &[proxy]), "10.0.0.2");
+        assert_eq!(Api::get_client_ip_behind(&headers, &addr, &[proxy, "10.0.0.2".parse().unwrap()]), "1.2.3.4");
+        assert_eq!(Api::get_client_ip_behind(&HeaderMap::new(), &addr, &[proxy]), "10.0.0.1");
+    }
+
+    fn get(cached: &CachedResponse, headers: &[(HeaderName, &str)]) -> Response {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.insert(name, value.parse().unwrap());
+        }
+        Api::conditional(&header_map, cached, TEXT_PLAIN, "public, max-age=60".to_string())
+    }
+
+    fn header(response: &Response, name: HeaderName) -> &str {
+        response.headers().get(name).and_then(|e| e.to_str().ok()).unwrap_or_default()
+    }
+
+    #[test]
+    fn conditional_etag() {
+        let cached = CachedResponse::compressed("[1]".to_string(), None);
+        let response = get(&cached, &[]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, ETAG), cached.etag);
+        assert_eq!(header(&response, CACHE_CONTROL), "public, max-age=60");
+
+        assert_eq!(get(&cached, &[(IF_NONE_MATCH, &cached.etag)]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&cached, &[(IF_NONE_MATCH, &format!("\"other\", W/{}", cached.etag))]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&cached, &[(IF_NONE_MATCH, "*")]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&cached, &[(IF_NONE_MATCH, "\"other\"")]).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn conditional_gzip() {
+        let cached = CachedResponse::compressed("[1]".to_string(), None);
+        let gzip_etag = cached.gzip.as_ref().unwrap().1.clone();
+        let response = get(&cached, &[(ACCEPT_ENCODING, "br, gzip;q=0.8")]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, CONTENT_ENCODING), "gzip");
+        assert_eq!(header(&response, ETAG), gzip_etag);
+
+        let response = get(&cached, &[(ACCEPT_ENCODING, "gzip"), (IF_NONE_MATCH, &gzip_etag)]);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(header(&response, CONTENT_ENCODING), "");
+        // the representations don't share an ETag
+        assert_eq!(get(&cached, &[(IF_NONE_MATCH, &gzip_etag)]).status(), StatusCode::OK);
+        assert_eq!(get(&cached, &[(ACCEPT_ENCODING, "gzip"), (IF_NONE_MATCH, &cached.etag)]).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn conditional_if_modified_since() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+        let cached = CachedResponse::compressed("[1]".to_string(), Some(modified));
+        let response = get(&cached, &[]);
+        assert_eq!(header(&response, LAST_MODIFIED), "Tue, 14 Nov 2023 22:13:20 GMT");
 
+        assert_eq!(get(&cached, &[(IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&cached, &[(IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:21 GMT")]).status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(get(&cached, &[(IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:19 GMT")]).status(), StatusCode::OK);
+        assert_eq!(get(&cached, &[(IF_MODIFIED_SINCE, "yesterday")]).status(), StatusCode::OK);
+        // If-None-Match wins
+        assert_eq!(get(&cached, &[(IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT"), (IF_NONE_MATCH, "\"other\"")]).status(), StatusCode::OK);
+
+        let unknown = CachedResponse::compressed("[1]".to_string(), None);
+        assert_eq!(get(&unknown, &[(IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")]).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn conditional_no_cache() {
+        let cached = CachedResponse::compressed(String::new(), None);
+        let response = get(&cached, &[(ACCEPT_ENCODING, "gzip")]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, CACHE_CONTROL), "no-cache");
+        assert_eq!(header(&response, LAST_MODIFIED), "");
+    }
+}
@@ -3,7 +3,7 @@ use std::{time::{Instant, Duration}, sync::Arc};
 use tokio::sync::RwLock;
 use tracing::log;
 
-use crate::{event_service::EventService, api_season_service::ApiSeasonService, stats_service::StatsService, player_service::PlayerService, models_api::{game_details::ApiGameDetails, report::GameStatus}};
+use crate::{event_service::EventService, head_to_head_service::HeadToHeadService, api_season_service::ApiSeasonService, stats_service::StatsService, player_service::PlayerService, models_api::{game_details::ApiGameDetails, report::GameStatus}};
 
 
 #[derive(Clone)]
@@ -22,12 +22,14 @@ impl ApiGameDetailsService {
         let game = self.api_season_service.read().await.read_game(game_uuid);
 
         if let Some(GameStatus::Coming) = game.as_ref().map(|e| e.status.clone()) {
-            return Some(ApiGameDetails { game: game.unwrap(), events: vec!(), stats: None, players: vec![] });
+            let game = game.unwrap();
+            let head_to_head = HeadToHeadService::read_summary(&game.home_team_code, &game.away_team_code);
+            return Some(ApiGameDetails { game, events: vec!(), stats: None, players: vec![], head_to_head });
         }
 
         let game = game.as_ref()?;
         let (events, stats, players) = futures::join!(
-            EventService::update(&game.season, game_uuid, throttle_s),
+            EventService::update(&game.league, &game.season, game_uuid, throttle_s),
             StatsService::update(&game.league, &game.season, game_uuid, throttle_s),
             PlayerService::update(&game.league, game_uuid, throttle_s),
         );
@@ -37,6 +39,7 @@ impl ApiGameDetailsService {
             events: events.unwrap_or_default().into_iter().rev().collect(),
             stats,
             players,
+            head_to_head: None,
         });
 
         log::debug!("[API.DETAILS] read {:.2?}", before.elapsed());
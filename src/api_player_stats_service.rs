@@ -1,15 +1,14 @@
-use std::{collections::HashMap, time::Instant};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 use tracing::log;
 
-use crate::{player_service::PlayerService, db::Db, models::Season, models_api::{athlete::{ApiAthleteStats, ApiAthlete, ApiPlayerStats, ApiGoalkeeperStats}, game::ApiGame, report::GameStatus}};
+use crate::{player_service::PlayerService, db::Db, response_cache::{ResponseCache, CachedResponse}, models::Season, models_api::{athlete::{ApiAthleteStats, ApiAthlete, ApiPlayerStats, ApiGoalkeeperStats}, game::ApiGame, report::GameStatus}};
 
 
 /**
  * keys: id -> see all seasons, teams etc.
  * season + team -> Players[]
  */
-
 type Team = String;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -132,9 +131,31 @@ impl ApiPlayerStatsService {
             _ = career_db.write(id, p);
         }
 
+        ResponseCache::invalidate_prefix("players/");
         log::info!("[API.PLAYERSTATS] Finished in {:.0?}", before.elapsed());
     }
 
+    pub fn read_career_cached(player_id: i32) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("players/career/{}", player_id), || {
+            let db = ApiPlayerStatsService::get_player_career_db();
+            (db.read_raw(&player_id), db.last_modified(&player_id))
+        })
+    }
+
+    pub fn read_team_cached(key: &TeamSeasonKey) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("players/team/{}", key), || {
+            let db = ApiPlayerStatsService::get_team_player_db();
+            (db.read_raw(key), db.last_modified(key))
+        })
+    }
+
+    pub fn read_season_cached(season: &Season) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("players/season/{}", season), || {
+            let db = ApiPlayerStatsService::get_season_player_db();
+            (db.read_raw(season), db.last_modified(season))
+        })
+    }
+
     pub fn get_player_career_db() -> Db<i32, Vec<ApiAthlete>> {
         Db::<i32, Vec<ApiAthlete>>::new("v2_api_player_career")
     }
@@ -202,9 +223,9 @@ mod tests {
         assert_eq!(stats.toi_s, 817 * 2);
 
         let team_db = ApiPlayerStatsService::get_team_player_db();
-        let stored_team = team_db.read(&TeamSeasonKey(crate::models::Season::Season2022, team.to_string())).unwrap();
+        let stored_team = team_db.read(&TeamSeasonKey(crate::models::Season(2022), team.to_string())).unwrap();
         assert_eq!(stored_team.len(), 1);
-        let team_player = stored_team.get(0).unwrap();
+        let team_player = stored_team.first().unwrap();
         assert_eq!(team_player.id, stored_player[0].id);
         let team_stats = match team_player.stats.clone() {
             ApiAthleteStats::Player(a) => a,
@@ -304,7 +325,7 @@ mod tests {
             played: true,
             game_type: crate::models::GameType::Season,
             league: crate::models::League::SHL,
-            season: crate::models::Season::Season2022,
+            season: crate::models::Season(2022),
             gametime: None,
             votes: None,
         }
@@ -1,10 +1,11 @@
-use std::{time::Instant, sync::Arc, collections::HashMap};
+use std::{time::{Instant, SystemTime}, sync::Arc, collections::HashMap};
 
-use chrono::{Utc, Duration};
+use chrono::{Utc, Duration, DateTime, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::log;
 
-use crate::{models::{Season, SeasonKey}, game_report_service::GameReportService, db::Db, models_external::season::{SeasonRsp, SeasonGame}, models_api::{game::ApiGame, report::{GameStatus, ApiGameReport}, vote::VotePerGame}};
+use crate::{models::{Season, SeasonKey, League, GameType}, game_report_service::GameReportService, head_to_head_service::HeadToHeadService, feed_service::FeedService, db::Db, models_external::season::{SeasonRsp, SeasonGame}, models_api::{game::ApiGame, report::{GameStatus, ApiGameReport}, vote::VotePerGame}};
 
 impl SeasonGame {
     pub fn is_potentially_live(&self) -> bool {
@@ -13,9 +14,132 @@ impl SeasonGame {
     }
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GamesView {
+    Today,
+    Upcoming, // not yet finished, soonest first
+    Recent, // finished, latest first
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct GamesFilter {
+    pub team: Option<String>,
+    pub league: Option<League>,
+    pub game_type: Option<GameType>,
+    pub status: Option<GameStatus>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>, // inclusive
+    pub view: Option<GamesView>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl GamesFilter {
+    pub fn apply(&self, games: Vec<ApiGame>, now: DateTime<Utc>) -> Vec<ApiGame> {
+        let today = now.date_naive();
+        let mut games: Vec<ApiGame> = games.into_iter()
+            .filter(|e| self.team.as_ref().map(|t| &e.home_team_code == t || &e.away_team_code == t).unwrap_or(true))
+            .filter(|e| self.league.as_ref().map(|l| &e.league == l).unwrap_or(true))
+            .filter(|e| self.game_type.as_ref().map(|t| &e.game_type == t).unwrap_or(true))
+            .filter(|e| self.status.as_ref().map(|s| &e.status == s).unwrap_or(true))
+            .filter(|e| self.from.map(|d| e.start_date_time.date_naive() >= d).unwrap_or(true))
+            .filter(|e| self.to.map(|d| e.start_date_time.date_naive() <= d).unwrap_or(true))
+            .filter(|e| match self.view {
+                Some(GamesView::Today) => e.start_date_time.date_naive() == today,
+                Some(GamesView::Upcoming) => !e.played,
+                Some(GamesView::Recent) => e.played,
+                None => true,
+            })
+            .collect();
+        match self.view {
+            Some(GamesView::Recent) => games.sort_by_key(|e| std::cmp::Reverse(e.start_date_time)),
+            Some(_) => games.sort_by_key(|e| e.start_date_time),
+            None => {},
+        }
+        games.into_iter()
+            .skip(self.offset.unwrap_or_default())
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SeasonChanges {
+    season: Season,
+    start: DateTime<Utc>, // changes before this are not known
+    games: HashMap<String, DateTime<Utc>>,
+}
+
+/**
+ * Latest change per game, stored per season so cursors survive a restart. A cursor is the time of the latest change in millis
+ */
+struct ChangeLog {
+    last: DateTime<Utc>,
+    seasons: HashMap<Season, SeasonChanges>,
+    db: Db<Season, SeasonChanges>,
+}
+impl ChangeLog {
+    fn new() -> ChangeLog {
+        let db = Db::<Season, SeasonChanges>::new("v2_game_changes");
+        let seasons: HashMap<Season, SeasonChanges> = db.read_all().into_iter().map(|e| (e.season.clone(), e)).collect();
+        let last = seasons.values().flat_map(|e| e.games.values()).max().copied().unwrap_or_else(ChangeLog::now);
+        ChangeLog { last, seasons, db }
+    }
+
+    // cursors are in millis, so are the changes
+    fn now() -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(Utc::now().timestamp_millis()).unwrap()
+    }
+
+    fn get_start(&self) -> DateTime<Utc> {
+        self.seasons.values().map(|e| e.start).min().unwrap_or(self.last)
+    }
+
+    fn add(&mut self, season: &Season, game_uuids: &[&str]) {
+        if game_uuids.is_empty() {
+            return;
+        }
+        // strictly increasing, so a cursor never splits changes made at the same time
+        let now = ChangeLog::now().max(self.last + Duration::milliseconds(1));
+        let start = self.get_start();
+        let changes = self.seasons.entry(season.clone())
+            .or_insert_with(|| SeasonChanges { season: season.clone(), start, games: HashMap::new() });
+        for game_uuid in game_uuids {
+            changes.games.insert(game_uuid.to_string(), now);
+        }
+        self.last = now;
+        _ = self.db.write(season, changes);
+    }
+
+    fn get_cursor(&self) -> String {
+        self.last.timestamp_millis().to_string()
+    }
+
+    /**
+     * None if the changes since the cursor or timestamp are no longer known
+     */
+    fn get_since(&self, cursor: Option<&str>, since: Option<DateTime<Utc>>) -> Option<Vec<&str>> {
+        let since = match cursor {
+            Some(cursor) => Utc.timestamp_millis_opt(cursor.parse().ok()?).single()?,
+            None => since?,
+        };
+        if since < self.get_start() {
+            return None;
+        }
+        let mut changes: Vec<(&DateTime<Utc>, &str)> = self.seasons.values()
+            .flat_map(|e| e.games.iter().map(|(game_uuid, time)| (time, game_uuid.as_str())))
+            .filter(|e| *e.0 > since)
+            .collect();
+        changes.sort_by(|a, b| b.cmp(a));
+        Some(changes.into_iter().map(|e| e.1).collect())
+    }
+}
+
 pub struct ApiSeasonService {
     current_season_in_mem: Vec<ApiGame>,
     rest_games: HashMap<String, ApiGame>,
+    change_log: ChangeLog,
     pub db: Db<Season, Vec<ApiGame>>,
 }
 pub type SafeApiSeasonService = Arc<RwLock<ApiSeasonService>>;
@@ -24,6 +148,7 @@ impl ApiSeasonService {
         Arc::new(RwLock::new(ApiSeasonService { 
             current_season_in_mem: vec!(),
             rest_games: HashMap::new(),
+            change_log: ChangeLog::new(),
             db: Db::<Season, Vec<ApiGame>>::new("v2_season_decorated")
         }))
     }
@@ -72,7 +197,15 @@ impl ApiSeasonService {
         .collect();
 
         log::info!("[API.SEASON] Decorated {season} {} games {:.2?}", decorated_games.len(), before.elapsed());
+        let previous = self.db.read(season).unwrap_or_default();
+        let changed: Vec<&str> = decorated_games.iter()
+            .filter(|game| previous.iter().find(|e| e.game_uuid == game.game_uuid) != Some(game))
+            .map(|e| e.game_uuid.as_str())
+            .collect();
+        self.change_log.add(season, &changed);
         _ = self.db.write(season, &decorated_games);
+        HeadToHeadService::invalidate();
+        FeedService::invalidate();
         if season.is_current() {
             self.current_season_in_mem = decorated_games.clone();
         } else {
@@ -84,33 +217,77 @@ impl ApiSeasonService {
     }
 
     pub fn update_from_report(&mut self, report: &ApiGameReport) -> Option<ApiGame> {
-        let mut result = None;
-        if let Some(pos) = self.current_season_in_mem.iter_mut().find(|e| e.game_uuid == report.game_uuid) {
-            pos.status = report.status.clone();
-            pos.played = report.status == GameStatus::Finished;
-            pos.home_team_result = report.home_team_result;
-            pos.away_team_result = report.away_team_result;
-            pos.overtime = report.overtime.unwrap_or(pos.overtime);
-            pos.shootout = report.shootout.unwrap_or(pos.shootout);
-            pos.gametime = Some(report.gametime.clone());
-            result = Some(pos.clone());
-            
-            _ = self.db.write(&pos.season.clone(), &self.current_season_in_mem);
+        let pos = self.current_season_in_mem.iter_mut().find(|e| e.game_uuid == report.game_uuid)?;
+        let before = pos.clone();
+        pos.status = report.status.clone();
+        pos.played = report.status == GameStatus::Finished;
+        pos.home_team_result = report.home_team_result;
+        pos.away_team_result = report.away_team_result;
+        pos.overtime = report.overtime.unwrap_or(pos.overtime);
+        pos.shootout = report.shootout.unwrap_or(pos.shootout);
+        pos.gametime = Some(report.gametime.clone());
+        let result = pos.clone();
+        if result != before {
+            self.change_log.add(&result.season, &[&result.game_uuid]);
+            _ = self.db.write(&result.season, &self.current_season_in_mem);
+            if report.status == GameStatus::Finished {
+                HeadToHeadService::invalidate();
+                FeedService::invalidate();
+            }
         }
-        result
+        Some(result)
     }
 
     pub fn update_from_votes(&mut self, game_uuid: &str, votes: VotePerGame) {
         if let Some(pos) = self.current_season_in_mem.iter_mut().find(|e| e.game_uuid == game_uuid) {
-            pos.votes = Some(votes.into());
-            _ = self.db.write(&pos.season.clone(), &self.current_season_in_mem);
+            let votes = Some(votes.into());
+            if pos.votes != votes {
+                pos.votes = votes;
+                let season = pos.season.clone();
+                self.change_log.add(&season, &[game_uuid]);
+                _ = self.db.write(&season, &self.current_season_in_mem);
+            }
+        }
+    }
+
+    /**
+     * Events are not part of the game, but clients syncing it still have to fetch them again
+     */
+    pub fn update_from_event(&mut self, game_uuid: &str) {
+        if let Some(game) = self.read_game(game_uuid) {
+            self.change_log.add(&game.season, &[game_uuid]);
         }
     }
 
+    /**
+     * Games changed since the cursor or timestamp, latest change first, with a cursor for the next call.
+     * None if the client has to fetch every season again
+     */
+    pub fn read_changes(&self, cursor: Option<&str>, since: Option<DateTime<Utc>>) -> (String, Option<Vec<ApiGame>>) {
+        let games = self.change_log.get_since(cursor, since)
+            .map(|uuids| uuids.into_iter().filter_map(|e| self.read_game(e)).collect());
+        (self.change_log.get_cursor(), games)
+    }
+
     pub fn read_current_season(&self) -> Vec<ApiGame> {
         self.current_season_in_mem.clone()
     }
 
+    /**
+     * Past seasons are ordered by start time, the file is only read for seasons not loaded at startup
+     */
+    pub fn read_season(&self, season: &Season) -> Vec<ApiGame> {
+        if season.is_current() {
+            return self.current_season_in_mem.clone();
+        }
+        let mut games: Vec<ApiGame> = self.rest_games.values().filter(|e| &e.season == season).cloned().collect();
+        if games.is_empty() {
+            return ApiSeasonService::read(season);
+        }
+        games.sort_by(|a, b| a.start_date_time.cmp(&b.start_date_time).then_with(|| a.game_uuid.cmp(&b.game_uuid)));
+        games
+    }
+
     pub fn read_current_season_game(&self, game_uuid: &str) -> Option<ApiGame> {
         self.current_season_in_mem
             .iter()
@@ -123,13 +300,13 @@ impl ApiSeasonService {
         if current_season_game.is_some() {
             current_season_game
         } else {
-            self.rest_games.get(&game_uuid.to_string()).cloned()
+            self.rest_games.get(game_uuid).cloned()
         }
     }
 
-    pub fn read_raw(season: &Season) -> String {
+    pub fn last_modified(season: &Season) -> Option<SystemTime> {
         let db: Db<Season, Vec<ApiGame>> = Db::new("v2_season_decorated");
-        db.read_raw(season)
+        db.last_modified(season)
     }
 
     pub fn read(season: &Season) -> Vec<ApiGame> {
@@ -155,7 +332,10 @@ mod tests {
 
     use crate::{models::{Season, SeasonKey, StringOrNum::Number}, models_external::season::{SeasonRsp, SeasonGame, GameTeamInfo, SeriesInfo, TeamNames}, models_api::report::{GameStatus, ApiGameReport}, game_report_service::GameReportService};
 
-    use super::ApiSeasonService;
+    use crate::models::{League, GameType};
+    use crate::models_api::game::ApiGame;
+
+    use super::{ApiSeasonService, GamesFilter, GamesView, ChangeLog};
 
     #[tokio::test]
     async fn test_report_is_used_pre() -> Result<(), ()> {
@@ -165,7 +345,7 @@ mod tests {
         let report = crate::models_api::report::ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period1, home_team_code: "LHF".to_string(), away_team_code: "SAIK".to_string(), home_team_result: 1, away_team_result: 1, overtime: Some(true), shootout: Some(true) };
         GameReportService::store("uuid", &report);
 
-        let season_key = SeasonKey(Season::Season2023, crate::models::League::SHL, crate::models::GameType::Season);
+        let season_key = SeasonKey(Season(2023), crate::models::League::SHL, crate::models::GameType::Season);
         let season_game = SeasonGame { 
             uuid: "uuid".to_string(), 
             awayTeamInfo: GameTeamInfo { code: "SAIK".to_string(), score: Number(0), names: Some(TeamNames { code: "SAIK".to_string(), long: "SAIK".to_string(), short: "SAIK".to_string() }) },
@@ -176,8 +356,8 @@ mod tests {
             overtime: false, 
             seriesInfo: SeriesInfo { code: crate::models::League::SHL },
         };
-        let result = service.write().await.update(&crate::models::Season::Season2023, &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
-        let api_game = result.get(0).unwrap();
+        let result = service.write().await.update(&crate::models::Season(2023), &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
+        let api_game = result.first().unwrap();
         assert_eq!(api_game.status, GameStatus::Coming);
         assert_eq!(api_game.gametime, None);
         assert_eq!(api_game.home_team_result, 0);
@@ -195,7 +375,7 @@ mod tests {
         let report = crate::models_api::report::ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period1, home_team_code: "LHF".to_string(), away_team_code: "SAIK".to_string(), home_team_result: 1, away_team_result: 1, overtime: Some(true), shootout: Some(true) };
         GameReportService::store("uuid", &report);
 
-        let season_key = SeasonKey(Season::Season2023, crate::models::League::SHL, crate::models::GameType::Season);
+        let season_key = SeasonKey(Season(2023), crate::models::League::SHL, crate::models::GameType::Season);
         let season_game = SeasonGame { 
             uuid: "uuid".to_string(), 
             awayTeamInfo: GameTeamInfo { code: "SAIK".to_string(), score: Number(0), names: Some(TeamNames { code: "SAIK".to_string(), long: "SAIK".to_string(), short: "SAIK".to_string() }) },
@@ -206,8 +386,8 @@ mod tests {
             overtime: false, 
             seriesInfo: SeriesInfo { code: crate::models::League::SHL },
         };
-        let result = service.write().await.update(&crate::models::Season::Season2023, &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
-        let api_game = result.get(0).unwrap();
+        let result = service.write().await.update(&crate::models::Season(2023), &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
+        let api_game = result.first().unwrap();
         assert_eq!(api_game.status, GameStatus::Period1);
         assert_eq!(api_game.gametime, Some("13:37".to_string()));
         assert_eq!(api_game.home_team_result, 1);
@@ -226,7 +406,7 @@ mod tests {
         let report = crate::models_api::report::ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period1, home_team_code: "LHF".to_string(), away_team_code: "SAIK".to_string(), home_team_result: 1, away_team_result: 1, overtime: Some(true), shootout: Some(true) };
         GameReportService::store("uuid", &report);
 
-        let season_key = SeasonKey(Season::Season2023, crate::models::League::SHL, crate::models::GameType::Season);
+        let season_key = SeasonKey(Season(2023), crate::models::League::SHL, crate::models::GameType::Season);
         let season_game = SeasonGame { 
             uuid: "uuid".to_string(), 
             awayTeamInfo: GameTeamInfo { code: "SAIK".to_string(), score: Number(0), names: Some(TeamNames { code: "SAIK".to_string(), long: "SAIK".to_string(), short: "SAIK".to_string() }) },
@@ -237,8 +417,8 @@ mod tests {
             overtime: false, 
             seriesInfo: SeriesInfo { code: crate::models::League::SHL },
         };
-        let result = service.write().await.update(&crate::models::Season::Season2023, &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
-        let api_game = result.get(0).unwrap();
+        let result = service.write().await.update(&crate::models::Season(2023), &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
+        let api_game = result.first().unwrap();
         assert_eq!(api_game.status, GameStatus::Finished);
         assert_eq!(api_game.gametime, None);
         assert_eq!(api_game.home_team_result, 0);
@@ -256,7 +436,7 @@ mod tests {
         let report = crate::models_api::report::ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period1, home_team_code: "LHF".to_string(), away_team_code: "SAIK".to_string(), home_team_result: 1, away_team_result: 1, overtime: Some(true), shootout: Some(true) };
         GameReportService::store("uuid", &report);
 
-        let season_key = SeasonKey(Season::Season2023, crate::models::League::SHL, crate::models::GameType::Season);
+        let season_key = SeasonKey(Season(2023), crate::models::League::SHL, crate::models::GameType::Season);
         let season_game = SeasonGame { 
             uuid: "uuid".to_string(), 
             awayTeamInfo: GameTeamInfo { code: "SAIK".to_string(), score: Number(0), names: Some(TeamNames { code: "SAIK".to_string(), long: "SAIK".to_string(), short: "SAIK".to_string() }) },
@@ -267,12 +447,81 @@ mod tests {
             overtime: false, 
             seriesInfo: SeriesInfo { code: crate::models::League::SHL },
         };
-        service.write().await.update(&crate::models::Season::Season2023, &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
+        service.write().await.update(&crate::models::Season(2023), &[(season_key, SeasonRsp { gameInfo: vec![season_game], teamList: vec![] })], HashMap::new());
 
         service.write().await.update_from_report(&ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period2, home_team_code: "SAIK".to_string(), away_team_code: "LHF".to_string(), home_team_result: 1, away_team_result: 1, overtime: None, shootout: None });
         let all_games = service.read().await.read_current_season();
-        let updated = all_games.get(0).unwrap();
+        let updated = all_games.first().unwrap();
         assert_eq!(updated.status, GameStatus::Period2);
         Ok(())
     }
+
+    #[test]
+    fn games_filter() {
+        let now = Utc::now();
+        let game = |uuid: &str, home: &str, away: &str, start: chrono::DateTime<Utc>, played: bool, league: League| ApiGame {
+            game_uuid: uuid.to_string(),
+            home_team_code: home.to_string(),
+            away_team_code: away.to_string(),
+            home_team_result: 0,
+            away_team_result: 0,
+            start_date_time: start,
+            status: match played { true => GameStatus::Finished, false => GameStatus::Coming },
+            shootout: false,
+            overtime: false,
+            played,
+            game_type: GameType::Season,
+            league,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        };
+        let games = vec![
+            game("g1", "LHF", "FHC", now - Duration::days(3), true, League::SHL),
+            game("g2", "TIK", "LHF", now - Duration::days(1), true, League::SHL),
+            game("g3", "LHF", "MODO", now, false, League::SHL),
+            game("g4", "AIK", "BIK", now + Duration::days(1), false, League::HA),
+            game("g5", "FHC", "LHF", now + Duration::days(2), false, League::SHL),
+        ];
+        let uuids = |filter: GamesFilter| filter.apply(games.clone(), now).into_iter().map(|e| e.game_uuid).collect::<Vec<String>>();
+
+        assert_eq!(uuids(GamesFilter::default()).len(), 5);
+        assert_eq!(uuids(GamesFilter { team: Some("LHF".to_string()), ..Default::default() }), vec!["g1", "g2", "g3", "g5"]);
+        assert_eq!(uuids(GamesFilter { league: Some(League::HA), ..Default::default() }), vec!["g4"]);
+        assert_eq!(uuids(GamesFilter { status: Some(GameStatus::Finished), ..Default::default() }), vec!["g1", "g2"]);
+        assert_eq!(uuids(GamesFilter { from: Some((now - Duration::days(1)).date_naive()), to: Some(now.date_naive()), ..Default::default() }), vec!["g2", "g3"]);
+        assert_eq!(uuids(GamesFilter { view: Some(GamesView::Today), ..Default::default() }), vec!["g3"]);
+        assert_eq!(uuids(GamesFilter { view: Some(GamesView::Recent), team: Some("LHF".to_string()), limit: Some(1), ..Default::default() }), vec!["g2"]);
+        assert_eq!(uuids(GamesFilter { view: Some(GamesView::Upcoming), offset: Some(1), limit: Some(2), ..Default::default() }), vec!["g4", "g5"]);
+    }
+
+    #[test]
+    fn change_log() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let season = Season(1990);
+        let mut log = ChangeLog::new();
+        let start = log.get_cursor();
+        assert_eq!(log.get_since(Some(&start), None), Some(vec![]));
+
+        log.add(&season, &["cl1", "cl2"]);
+        let cursor = log.get_cursor();
+        log.add(&season, &["cl1"]);
+        log.add(&season, &["cl3"]);
+        log.add(&season, &[]);
+
+        assert_eq!(log.get_since(Some(&start), None), Some(vec!["cl3", "cl1", "cl2"]));
+        assert_eq!(log.get_since(Some(&cursor), None), Some(vec!["cl3", "cl1"]));
+        assert_eq!(log.get_since(Some(&log.get_cursor()), None), Some(vec![]));
+        assert_eq!(log.get_since(None, Some(log.get_start())), Some(vec!["cl3", "cl1", "cl2"]));
+
+        // unparsable cursor, before the first change or no cursor at all
+        assert_eq!(log.get_since(Some("nope"), None), None);
+        assert_eq!(log.get_since(None, Some(log.get_start() - Duration::seconds(1))), None);
+        assert_eq!(log.get_since(None, None), None);
+
+        // survives a restart, other tests may add their own games meanwhile
+        let restarted = ChangeLog::new();
+        let since_cursor: Vec<&str> = restarted.get_since(Some(&cursor), None).unwrap().into_iter().filter(|e| e.starts_with("cl")).collect();
+        assert_eq!(since_cursor, vec!["cl3", "cl1"]);
+    }
 }
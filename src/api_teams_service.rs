@@ -1,6 +1,8 @@
+use std::sync::Arc;
+
 use serde::{Serialize, Deserialize};
 
-use crate::{db::Db, models::League};
+use crate::{db::Db, models::League, response_cache::{ResponseCache, CachedResponse}};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -22,8 +24,15 @@ impl ApiTeamsService {
         ApiTeamsService::get_db().read(&"teams".to_string()).unwrap_or_default()
     }
 
-    pub fn read_raw() -> String {
-        ApiTeamsService::get_db().read_raw(&"teams".to_string())
+    /**
+     * Teams are only written by hand, cached until restart
+     */
+    pub fn read_cached() -> Arc<CachedResponse> {
+        ResponseCache::get_or_load("teams", || {
+            let db = ApiTeamsService::get_db();
+            let key = "teams".to_string();
+            (db.read_raw(&key), db.last_modified(&key))
+        })
     }
 
     fn get_db() -> Db<String, Vec<ApiTeam>> {
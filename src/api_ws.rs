@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{sync::{Arc, Mutex}, time::Duration};
 
 use axum::extract::ws::{WebSocket, Message};
 use futures::{StreamExt, SinkExt};
@@ -6,14 +6,15 @@ use serde::{Serialize, Deserialize};
 use tokio::select;
 use tracing::log;
 
-use crate::{api::ApiState, models_api::{event::ApiGameEvent, report::ApiGameReport, stats::ApiGameStats}};
+use crate::{api::ApiState, models_api::{event::ApiGameEvent, report::ApiGameReport, stats::ApiGameStats, standings::LiveStandings}, status_service::Status};
 
 
 
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct WsMsg {
-    pub game_uuid: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub game_uuid: Option<String>,
     
     #[serde(flatten)]
     pub body: WsMsgBody,
@@ -24,23 +25,58 @@ pub struct WsMsg {
 pub enum WsMsgBody {
     Event { event: ApiGameEvent },
     Report { report: ApiGameReport },
-    Stats { stats: ApiGameStats }
+    Stats { stats: ApiGameStats },
+    Status { statuses: Vec<Status> },
+    LiveStandings { standings: LiveStandings },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct WsReq {
-    pub game_uuid: String,
+    #[serde(default)]
+    pub game_uuid: Option<String>,
+    #[serde(default)]
+    pub subscribe: Vec<WsTopic>,
+}
+
+/**
+ * Messages not tied to a game, only sent to clients that subscribed so older apps never see them
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all="snake_case")]
+pub enum WsTopic {
+    Status,
+    LiveStandings,
+}
+
+impl WsMsg {
+    fn get_topic(&self) -> Option<WsTopic> {
+        match self.body {
+            WsMsgBody::Status { .. } => Some(WsTopic::Status),
+            WsMsgBody::LiveStandings { .. } => Some(WsTopic::LiveStandings),
+            _ => None,
+        }
+    }
 }
 
 
 impl From<ApiGameEvent> for WsMsg {
     fn from(event: ApiGameEvent) -> Self {
-        WsMsg { game_uuid: event.game_uuid.clone(), body: WsMsgBody::Event { event } }
+        WsMsg { game_uuid: Some(event.game_uuid.clone()), body: WsMsgBody::Event { event } }
     }
 }
 impl From<ApiGameReport> for WsMsg {
     fn from(report: ApiGameReport) -> Self {
-        WsMsg { game_uuid: report.game_uuid.clone(), body: WsMsgBody::Report { report } }
+        WsMsg { game_uuid: Some(report.game_uuid.clone()), body: WsMsgBody::Report { report } }
+    }
+}
+impl From<Vec<Status>> for WsMsg {
+    fn from(statuses: Vec<Status>) -> Self {
+        WsMsg { game_uuid: None, body: WsMsgBody::Status { statuses } }
+    }
+}
+impl From<LiveStandings> for WsMsg {
+    fn from(standings: LiveStandings) -> Self {
+        WsMsg { game_uuid: None, body: WsMsgBody::LiveStandings { standings } }
     }
 }
 pub struct ApiWs {
@@ -51,20 +87,31 @@ impl ApiWs {
     pub async fn handle(stream: WebSocket, state: ApiState) {
         let (mut sender, mut receiver) = stream.split();
         let mut broadcast_receiver = state.broadcast_sender.subscribe();
+        let topics: Arc<Mutex<Vec<WsTopic>>> = Arc::new(Mutex::new(vec![]));
 
         log::info!("[API.WS] Open, in total = {}", ApiWs::update_nr_connections(1, &state).await);
 
-        let receive_handle = tokio::spawn(async move {
-            while let Some(Ok(msg)) = receiver.next().await {
-                if let Some(ws_req) = msg.into_text().ok().and_then(|e| serde_json::from_str::<WsReq>(&e).ok()) {
-                    log::info!("[API.WS] Req {:?}", ws_req);
+        let receive_handle = {
+            let topics = topics.clone();
+            tokio::spawn(async move {
+                while let Some(Ok(msg)) = receiver.next().await {
+                    if let Some(ws_req) = msg.into_text().ok().and_then(|e| serde_json::from_str::<WsReq>(&e).ok()) {
+                        log::info!("[API.WS] Req {:?}", ws_req);
+                        let mut topics = topics.lock().unwrap();
+                        for topic in ws_req.subscribe {
+                            if !topics.contains(&topic) {
+                                topics.push(topic);
+                            }
+                        }
+                    }
                 }
-            }
-        });
+            })
+        };
         _ = tokio::spawn(async move {
             loop {
                 let msg = select! {
                     msg = broadcast_receiver.recv() => match msg {
+                        Ok(msg) if msg.get_topic().is_some_and(|e| !topics.lock().unwrap().contains(&e)) => continue,
                         Ok(msg) => Message::Text(serde_json::to_string(&msg).unwrap_or_default()),
                         Err(e) => {
                             log::error!("[API.WS] broadcast receive {:?}", e);
@@ -94,4 +141,34 @@ impl ApiWs {
         *nr_ws += delta;
         *nr_ws
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models_api::report::{ApiGameReport, GameStatus};
+
+    use super::{WsMsg, WsReq, WsTopic};
+
+    #[test]
+    fn game_messages_keep_their_shape() {
+        let report = ApiGameReport { game_uuid: "uuid".to_string(), gametime: "13:37".to_string(), status: GameStatus::Period2, home_team_code: "LHF".to_string(), away_team_code: "FHC".to_string(), home_team_result: 1, away_team_result: 0, overtime: None, shootout: None };
+        let msg = WsMsg::from(report);
+        assert_eq!(msg.get_topic(), None);
+        let json: serde_json::Value = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["game_uuid"], "uuid");
+        assert_eq!(json["type"], "report");
+
+        assert_eq!(WsMsg::from(vec![]).get_topic(), Some(WsTopic::Status));
+    }
+
+    #[test]
+    fn parse_req() {
+        // what released apps send
+        let req: WsReq = serde_json::from_str(r#"{ "game_uuid": "uuid" }"#).unwrap();
+        assert_eq!(req.game_uuid.as_deref(), Some("uuid"));
+        assert!(req.subscribe.is_empty());
+
+        let req: WsReq = serde_json::from_str(r#"{ "subscribe": ["status", "live_standings"] }"#).unwrap();
+        assert_eq!(req.subscribe, vec![WsTopic::Status, WsTopic::LiveStandings]);
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc, Duration};
+
+use crate::models::GameType;
+use crate::models_api::{game::ApiGame, report::GameStatus};
+
+const GAME_LENGTH_MIN: i64 = 150;
+
+pub struct CalendarService;
+impl CalendarService {
+
+    /**
+     * iCalendar feed of the given games, team names looked up by code and falling back to the code.
+     * Events are keyed by game_uuid so calendar apps move them when the schedule changes
+     */
+    pub fn get(name: &str, games: &[ApiGame], team_names: &HashMap<String, String>, now: DateTime<Utc>) -> String {
+        let mut games: Vec<&ApiGame> = games.iter().collect();
+        games.sort_by_key(|e| e.start_date_time);
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//shl-server-rs//schedule//SV".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            format!("X-WR-CALNAME:{}", CalendarService::escape(name)),
+            "REFRESH-INTERVAL;VALUE=DURATION:PT1H".to_string(),
+            "X-PUBLISHED-TTL:PT1H".to_string(),
+        ];
+        for game in games {
+            let team = |code: &String| team_names.get(code).unwrap_or(code).clone();
+            let summary = match game.status {
+                GameStatus::Finished => format!("{} {} - {} {}{}", team(&game.home_team_code), game.home_team_result, game.away_team_result, team(&game.away_team_code), CalendarService::get_suffix(game)),
+                _ => format!("{} - {}", team(&game.home_team_code), team(&game.away_team_code)),
+            };
+            let description = match game.game_type {
+                GameType::Season => format!("{} grundserie", game.league),
+                GameType::PlayOff => format!("{} slutspel", game.league),
+                GameType::Demotion => format!("{} kval", game.league),
+            };
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}@shl-server-rs", game.game_uuid),
+                format!("DTSTAMP:{}", CalendarService::format_time(now)),
+                format!("DTSTART:{}", CalendarService::format_time(game.start_date_time)),
+                format!("DTEND:{}", CalendarService::format_time(game.start_date_time + Duration::minutes(GAME_LENGTH_MIN))),
+                format!("SUMMARY:{}", CalendarService::escape(&summary)),
+                format!("DESCRIPTION:{}", CalendarService::escape(&description)),
+                "STATUS:CONFIRMED".to_string(),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines.iter()
+            .map(|e| CalendarService::fold(e))
+            .map(|e| format!("{e}\r\n"))
+            .collect()
+    }
+
+    fn get_suffix(game: &ApiGame) -> &'static str {
+        match (game.overtime, game.shootout) {
+            (_, true) => " (straffar)",
+            (true, false) => " (övertid)",
+            _ => "",
+        }
+    }
+
+    fn format_time(time: DateTime<Utc>) -> String {
+        time.format("%Y%m%dT%H%M%SZ").to_string()
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace(';', "\\;")
+            .replace(',', "\\,")
+            .replace('\n', "\\n")
+    }
+
+    /**
+     * Lines longer than 75 octets continue on the next line after a space, never splitting a character
+     */
+    fn fold(line: &str) -> String {
+        let mut folded = String::new();
+        let mut len = 0;
+        for c in line.chars() {
+            if len + c.len_utf8() > 75 {
+                folded.push_str("\r\n ");
+                len = 1;
+            }
+            folded.push(c);
+            len += c.len_utf8();
+        }
+        folded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Utc, TimeZone, Duration};
+
+    use crate::{models::{League, Season, GameType}, models_api::{game::ApiGame, report::GameStatus}};
+
+    use super::CalendarService;
+
+    #[test]
+    fn calendar() {
+        let start = Utc.with_ymd_and_hms(2023, 9, 16, 16, 0, 0).unwrap();
+        let game = |uuid: &str, home: &str, away: &str, days: i64, status: GameStatus| ApiGame {
+            game_uuid: uuid.to_string(),
+            home_team_code: home.to_string(),
+            away_team_code: away.to_string(),
+            home_team_result: if status == GameStatus::Finished { 3 } else { 0 },
+            away_team_result: if status == GameStatus::Finished { 2 } else { 0 },
+            start_date_time: start + Duration::days(days),
+            played: status == GameStatus::Finished,
+            status,
+            shootout: false,
+            overtime: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        };
+        let games = vec![
+            game("calendar_game2", "FHC", "LHF", 2, GameStatus::Coming),
+            game("calendar_game1", "LHF", "TIK", 0, GameStatus::Finished),
+        ];
+        let names = HashMap::from([("LHF".to_string(), "Luleå Hockey".to_string()), ("FHC".to_string(), "Frölunda HC".to_string())]);
+
+        let ics = CalendarService::get("LHF, SHL", &games, &names, start);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("X-WR-CALNAME:LHF\\, SHL\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.find("UID:calendar_game1@shl-server-rs").unwrap() < ics.find("UID:calendar_game2@shl-server-rs").unwrap());
+        assert!(ics.contains("DTSTART:20230916T160000Z\r\nDTEND:20230916T183000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Luleå Hockey 3 - 2 TIK (övertid)\r\n"));
+        assert!(ics.contains("SUMMARY:Frölunda HC - Luleå Hockey\r\n"));
+        assert!(ics.lines().all(|e| e.len() <= 75));
+    }
+
+    #[test]
+    fn fold() {
+        let line = format!("SUMMARY:{}", "ö".repeat(50));
+        let folded = CalendarService::fold(&line);
+        assert!(folded.split("\r\n").all(|e| e.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::{fs, net::IpAddr};
 
-use crate::models::League;
+use crate::models::{LeagueInfo, SeasonInfo, PlayoffFormat, default_leagues};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -32,7 +32,19 @@ pub struct Config {
     pub sse_file_append: bool,
 
     #[serde(default="default_false")]
-    pub poll: bool
+    pub poll: bool,
+
+    #[serde(default)]
+    pub seasons: Vec<SeasonInfo>,
+
+    #[serde(default)]
+    pub leagues: Vec<LeagueInfo>,
+
+    #[serde(default)]
+    pub playoff_formats: Vec<PlayoffFormat>,
+
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>, // peers whose x-forwarded-for is believed
 }
 
 fn default_db_path() -> String {
@@ -52,10 +64,10 @@ fn default_false() -> bool {
 }
 
 impl Config {
-    pub fn get_url(&self, league: &League) -> &str {
-        match league {
-            League::HA => self.ha_url.as_str(),
-            League::SHL => self.shl_url.as_str(),
+    pub fn get_leagues(&self) -> Vec<LeagueInfo> {
+        match self.leagues.is_empty() {
+            true => default_leagues(&self.shl_url, &self.ha_url),
+            false => self.leagues.clone(),
         }
     }
 }
@@ -85,6 +85,16 @@ impl<K: Display + Clone, V: DeserializeOwned + Serialize + Clone> Db<K, V> {
         }
     }
 
+    pub fn delete(&self, key: &K) -> std::io::Result<()> {
+        std::fs::remove_file(self.get_path(&key.to_string()))
+    }
+
+    pub fn last_modified(&self, key: &K) -> Option<SystemTime> {
+        std::fs::metadata(self.get_path(&key.to_string()))
+            .and_then(|e| e.modified())
+            .ok()
+    }
+
     pub fn is_stale(&self, key: &K, delta_s: Option<Duration>) -> bool {
         let path = self.get_path(&key.to_string());
         std::fs::metadata(path)
@@ -1,6 +1,6 @@
 use std::{time::Duration, str::FromStr, fmt::Display};
 
-use crate::{db::Db, rest_client::{self}, models_external::{event::{PlayByPlayType, Penalty, Shot, Goal, LiveEvent, EventType, PeriodType}, self}, models::{ParseStringError, Season, StringOrNum}, models_api::event::*};
+use crate::{db::Db, rest_client::{self}, models_external::{event::{PlayByPlayType, Penalty, Shot, Goal, LiveEvent, EventType, PeriodType}, self}, models::{League, ParseStringError, Season, StringOrNum}, models_api::event::*};
 
 
 impl FromStr for Player {
@@ -155,20 +155,20 @@ fn add_period_events(raw_events: Vec<LiveEvent>) -> Vec<LiveEvent> {
 pub struct EventService;
 impl EventService {
  
-    pub async fn update(season: &Season, game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
-        match season {
-            Season::Season2023 => EventService::update_2023_season(game_uuid, throttle_s).await,
-            _ => EventService::update_older_season(game_uuid, throttle_s).await,
+    pub async fn update(league: &League, season: &Season, game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
+        match season.is_legacy_api() {
+            false => EventService::update_2023_season(league, game_uuid, throttle_s).await,
+            true => EventService::update_older_season(league, game_uuid, throttle_s).await,
         }
     }
 
-    async fn update_2023_season(game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
+    async fn update_2023_season(league: &League, game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
         let db_raw: Db<String, Vec<LiveEvent>> = Db::new("v2_events_raw_2023");
 
         let raw_events = if !db_raw.is_stale(&game_uuid.to_string(), throttle_s) {
             db_raw.read(&game_uuid.to_string()).unwrap_or_default()
         } else {
-            let mut raw_events = rest_client::get_events_2023(game_uuid).await.unwrap_or_default();
+            let mut raw_events = rest_client::get_events_2023(league, game_uuid).await.unwrap_or_default();
             raw_events.reverse();
             let result = add_period_events(raw_events);
             _ = db_raw.write(&game_uuid.to_string(), &result);
@@ -179,13 +179,13 @@ impl EventService {
     }
     
 
-    async fn update_older_season(game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
+    async fn update_older_season(league: &League, game_uuid: &str, throttle_s: Option<Duration>) -> Option<Vec<ApiGameEvent>> {
         let db_raw: Db<String, Vec<models_external::event::PlayByPlay>> = Db::new("v2_events_raw");
 
         let raw_events = if !db_raw.is_stale(&game_uuid.to_string(), throttle_s) {
             db_raw.read(&game_uuid.to_string()).unwrap_or_default()
         } else {
-            let raw_events = rest_client::get_events(game_uuid).await.unwrap_or_default();
+            let raw_events = rest_client::get_events(league, game_uuid).await.unwrap_or_default();
             _ = db_raw.write(&game_uuid.to_string(), &raw_events);
             raw_events
         };
@@ -0,0 +1,255 @@
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{models::Season, models_api::{game::ApiGame, report::GameStatus}, stats_service::StatsService, player_service::PlayerService, event_service::EventService, api_player_stats_service::ApiPlayerStatsService};
+
+const GAME_COLUMNS: &[&str] = &["game_uuid", "league", "season", "game_type", "start_date_time", "status", "home_team_code", "away_team_code", "home_team_result", "away_team_result", "overtime", "shootout", "played"];
+const TEAM_STATS_COLUMNS: &[&str] = &["game_uuid", "home_team_code", "away_team_code", "home.g", "home.sog", "home.pim", "home.fow", "away.g", "away.sog", "away.pim", "away.fow"];
+const ATHLETE_COLUMNS: &[&str] = &["id", "first_name", "family_name", "jersey", "team_code", "position", "league", "season", "type", "gp", "g", "a", "d", "sog", "pim", "hits", "fow", "fol", "sw", "toi_s", "ga", "soga", "spga", "svs"];
+const EVENT_COLUMNS: &[&str] = &["game_uuid", "event_id", "revision", "status", "gametime", "type", "team", "player.jersey", "player.first_name", "player.family_name", "home_team_result", "away_team_result", "team_advantage", "reason", "penalty", "winner", "description"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportDataset {
+    Games,
+    TeamStats,
+    PlayerStats,
+    Events,
+    Players, // season totals
+}
+impl ExportDataset {
+    pub fn get_all() -> Vec<ExportDataset> {
+        vec![ExportDataset::Games, ExportDataset::TeamStats, ExportDataset::PlayerStats, ExportDataset::Events, ExportDataset::Players]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportDataset::Games => "games",
+            ExportDataset::TeamStats => "team_stats",
+            ExportDataset::PlayerStats => "player_stats",
+            ExportDataset::Events => "events",
+            ExportDataset::Players => "players",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+impl ExportFormat {
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn get_content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+pub struct ExportService;
+impl ExportService {
+
+    pub fn export(dataset: ExportDataset, format: ExportFormat, season: &Season, games: &[ApiGame]) -> String {
+        let (columns, rows) = ExportService::get_rows(dataset, season, games);
+        match format {
+            ExportFormat::Ndjson => ExportService::to_ndjson(&rows),
+            ExportFormat::Csv => ExportService::to_csv(&columns, &rows),
+        }
+    }
+
+    /**
+     * Every dataset of the season in one tar.gz, {season}/{dataset}.{format}
+     */
+    pub fn archive(format: ExportFormat, season: &Season, games: &[ApiGame]) -> std::io::Result<Vec<u8>> {
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let now = chrono::Utc::now().timestamp() as u64;
+        for dataset in ExportDataset::get_all() {
+            let data = ExportService::export(dataset, format, season, games);
+            let mut header = tar::Header::new_ustar();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(now);
+            let path = format!("{}/{}.{}", season.0, dataset.as_str(), format.get_extension());
+            archive.append_data(&mut header, path, data.as_bytes())?;
+        }
+        archive.into_inner()?.finish()
+    }
+
+    fn get_rows(dataset: ExportDataset, season: &Season, games: &[ApiGame]) -> (Vec<&'static str>, Vec<Value>) {
+        let played = games.iter().filter(|e| e.status != GameStatus::Coming);
+        match dataset {
+            ExportDataset::Games => {
+                let rows = games.iter()
+                    .map(|e| ApiGame { votes: None, gametime: None, ..e.clone() })
+                    .filter_map(|e| serde_json::to_value(e).ok())
+                    .collect();
+                (GAME_COLUMNS.to_vec(), rows)
+            },
+            ExportDataset::TeamStats => {
+                let rows = played
+                    .filter_map(|game| StatsService::read(&game.league, &game.season, &game.game_uuid).map(|stats| json!({
+                        "game_uuid": game.game_uuid,
+                        "home_team_code": game.home_team_code,
+                        "away_team_code": game.away_team_code,
+                        "home": stats.home,
+                        "away": stats.away,
+                    })))
+                    .collect();
+                (TEAM_STATS_COLUMNS.to_vec(), rows)
+            },
+            ExportDataset::PlayerStats => {
+                let rows = played
+                    .flat_map(|game| PlayerService::read(&game.league, &game.game_uuid).unwrap_or_default().into_iter()
+                        .filter_map(|e| serde_json::to_value(e).ok())
+                        .map(|e| ExportService::with_game_uuid(e, &game.game_uuid)))
+                    .collect();
+                ([&["game_uuid"], ATHLETE_COLUMNS].concat(), rows)
+            },
+            ExportDataset::Events => {
+                let rows = played
+                    .flat_map(|game| EventService::read(&game.game_uuid))
+                    .filter_map(|e| serde_json::to_value(e).ok())
+                    .collect();
+                (EVENT_COLUMNS.to_vec(), rows)
+            },
+            ExportDataset::Players => {
+                let rows = ApiPlayerStatsService::get_season_player_db().read(season).unwrap_or_default().into_iter()
+                    .filter_map(|e| serde_json::to_value(e).ok())
+                    .collect();
+                (ATHLETE_COLUMNS.to_vec(), rows)
+            },
+        }
+    }
+
+    fn with_game_uuid(mut row: Value, game_uuid: &str) -> Value {
+        if let Value::Object(e) = &mut row {
+            e.insert("game_uuid".to_string(), Value::String(game_uuid.to_string()));
+        }
+        row
+    }
+
+    fn to_ndjson(rows: &[Value]) -> String {
+        rows.iter().map(|e| format!("{e}\n")).collect()
+    }
+
+    /**
+     * Columns are dotted paths into the json rows, missing values are left empty
+     */
+    fn to_csv(columns: &[&str], rows: &[Value]) -> String {
+        let mut csv = format!("{}\r\n", columns.join(","));
+        for row in rows {
+            let fields: Vec<String> = columns.iter()
+                .map(|column| row.pointer(&format!("/{}", column.replace('.', "/"))))
+                .map(|e| match e {
+                    None | Some(Value::Null) => "".to_string(),
+                    Some(Value::String(e)) => e.clone(),
+                    Some(e) => e.to_string(),
+                })
+                .map(|e| ExportService::escape_csv(&e))
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    fn escape_csv(field: &str) -> String {
+        match field.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use chrono::{Utc, TimeZone};
+    use flate2::read::GzDecoder;
+    use tempdir::TempDir;
+
+    use crate::{models::{League, Season, GameType}, models_api::{game::ApiGame, report::GameStatus}};
+
+    use super::{ExportService, ExportDataset, ExportFormat, GAME_COLUMNS};
+
+    fn before() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+    }
+
+    fn get_game() -> ApiGame {
+        ApiGame {
+            game_uuid: "export_game1".to_string(),
+            home_team_code: "LHF".to_string(),
+            away_team_code: "FHC".to_string(),
+            home_team_result: 2,
+            away_team_result: 1,
+            start_date_time: Utc.with_ymd_and_hms(2016, 9, 16, 17, 0, 0).unwrap(),
+            status: GameStatus::Finished,
+            shootout: false,
+            overtime: false,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2016),
+            gametime: Some("60:00".to_string()),
+            votes: None,
+        }
+    }
+
+    #[test]
+    fn export_games() {
+        before();
+        let games = vec![get_game()];
+
+        let csv = ExportService::export(ExportDataset::Games, ExportFormat::Csv, &Season(2016), &games);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], GAME_COLUMNS.join(","));
+        assert_eq!(lines[1], "export_game1,SHL,Season2016,Season,2016-09-16T17:00:00Z,Finished,LHF,FHC,2,1,false,false,true");
+
+        let ndjson = ExportService::export(ExportDataset::Games, ExportFormat::Ndjson, &Season(2016), &games);
+        assert_eq!(ndjson.lines().count(), 1);
+        let game: ApiGame = serde_json::from_str(ndjson.lines().next().unwrap()).unwrap();
+        assert_eq!(game, ApiGame { gametime: None, ..get_game() });
+
+        // nothing stored for the game, only the header
+        let csv = ExportService::export(ExportDataset::Events, ExportFormat::Csv, &Season(2016), &games);
+        assert_eq!(csv.lines().count(), 1);
+    }
+
+    #[test]
+    fn csv_escape() {
+        let rows = vec![serde_json::json!({ "a": "x, \"y\"", "b": { "c": 1 } })];
+        assert_eq!(ExportService::to_csv(&["a", "b.c", "d"], &rows), "a,b.c,d\r\n\"x, \"\"y\"\"\",1,\r\n");
+    }
+
+    #[test]
+    fn archive() {
+        before();
+        let data = ExportService::archive(ExportFormat::Csv, &Season(2016), &[get_game()]).unwrap();
+        let mut archive = tar::Archive::new(GzDecoder::new(&data[..]));
+
+        let mut entries = vec![];
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            entries.push((entry.path().unwrap().to_str().unwrap().to_string(), content));
+        }
+        let paths: Vec<&str> = entries.iter().map(|e| e.0.as_str()).collect();
+        assert_eq!(paths, vec!["2016/games.csv", "2016/team_stats.csv", "2016/player_stats.csv", "2016/events.csv", "2016/players.csv"]);
+        assert!(entries[0].1.starts_with("game_uuid,league"));
+        assert_eq!(entries[0].1.lines().count(), 2);
+    }
+}
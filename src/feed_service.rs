@@ -0,0 +1,215 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc, Duration, SecondsFormat};
+
+use crate::{api_teams_service::TeamsMap, apn_client::ApnAlert, event_service::EventService, models_api::{game::ApiGame, event::{ApiGameEvent, ApiEventType, GameEndInfo}, report::GameStatus}, response_cache::{ResponseCache, CachedResponse}};
+
+const FEED_NR_GAMES: usize = 30;
+const PERIOD_MIN: i64 = 20;
+const INTERMISSION_MIN: i64 = 18;
+const GAME_LENGTH_MIN: i64 = 150;
+
+struct FeedEntry {
+    id: String,
+    updated: DateTime<Utc>,
+    alert: ApnAlert,
+}
+
+pub struct FeedService;
+impl FeedService {
+
+    /**
+     * The latest finished games with their events, read once per feed until a game finishes
+     */
+    pub fn read_cached(title: &str, feed_id: &str, mut games: Vec<ApiGame>, teams: &TeamsMap, user_teams: &[String]) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("feed/{feed_id}"), || {
+            games.retain(|e| e.status == GameStatus::Finished);
+            games.sort_by_key(|e| std::cmp::Reverse(e.start_date_time));
+            games.truncate(FEED_NR_GAMES);
+            let events = games.iter().map(|e| (e.game_uuid.clone(), EventService::read(&e.game_uuid))).collect();
+            (FeedService::get(title, feed_id, &games, &events, teams, user_teams), None)
+        })
+    }
+
+    pub fn invalidate() {
+        ResponseCache::invalidate_prefix("feed/");
+    }
+
+    /**
+     * Atom feed of results and goals, newest first, with the same texts as the push notifications
+     */
+    pub fn get(title: &str, feed_id: &str, games: &[ApiGame], events: &HashMap<String, Vec<ApiGameEvent>>, teams: &TeamsMap, user_teams: &[String]) -> String {
+        let mut entries: Vec<FeedEntry> = vec![];
+        for game in games.iter().filter(|e| e.status == GameStatus::Finished) {
+            let result = ApiGameEvent {
+                game_uuid: game.game_uuid.clone(),
+                event_id: "result".to_string(),
+                revision: 1,
+                status: GameStatus::Finished,
+                gametime: "".to_string(),
+                description: "".to_string(),
+                info: ApiEventType::GameEnd(GameEndInfo { winner: FeedService::get_winner(game) }),
+            };
+            entries.push(FeedEntry {
+                id: format!("{}:result", game.game_uuid),
+                updated: game.start_date_time + Duration::minutes(GAME_LENGTH_MIN),
+                alert: ApnAlert::from(game, &result, teams, user_teams),
+            });
+            let goals = events.get(&game.game_uuid).into_iter()
+                .flatten()
+                .filter(|e| matches!(e.info, ApiEventType::Goal(_)));
+            for goal in goals {
+                entries.push(FeedEntry {
+                    id: format!("{}:{}", game.game_uuid, goal.event_id),
+                    updated: FeedService::get_event_time(game, goal),
+                    alert: ApnAlert::from(game, goal, teams, user_teams),
+                });
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.updated));
+        let updated = entries.first().map(|e| e.updated).unwrap_or_default();
+
+        let mut xml = vec![
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string(),
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\">".to_string(),
+            format!("<id>urn:shl-server-rs:feed:{}</id>", FeedService::escape(feed_id)),
+            format!("<title>{}</title>", FeedService::escape(title)),
+            format!("<updated>{}</updated>", FeedService::format_time(updated)),
+            "<author><name>shl-server-rs</name></author>".to_string(),
+        ];
+        for entry in entries {
+            xml.extend([
+                "<entry>".to_string(),
+                format!("<id>urn:shl-server-rs:{}</id>", FeedService::escape(&entry.id)),
+                format!("<title>{}</title>", FeedService::escape(&entry.alert.title)),
+                format!("<updated>{}</updated>", FeedService::format_time(entry.updated)),
+                format!("<summary>{}</summary>", FeedService::escape(&entry.alert.body)),
+                "</entry>".to_string(),
+            ]);
+        }
+        xml.push("</feed>".to_string());
+        xml.join("\n")
+    }
+
+    fn get_winner(game: &ApiGame) -> Option<String> {
+        [&game.home_team_code, &game.away_team_code].into_iter()
+            .find(|e| game.did_team_win(e))
+            .cloned()
+    }
+
+    /**
+     * Events only carry the time within the period, estimated from the start with regular intermissions
+     */
+    fn get_event_time(game: &ApiGame, event: &ApiGameEvent) -> DateTime<Utc> {
+        let period = match event.status {
+            GameStatus::Period1 => 0,
+            GameStatus::Period2 => 1,
+            GameStatus::Period3 => 2,
+            _ => 3,
+        };
+        let (min, sec) = event.gametime.split_once(':')
+            .map(|(m, s)| (m.parse().unwrap_or(0), s.parse().unwrap_or(0)))
+            .unwrap_or((0, 0));
+        game.start_date_time + Duration::minutes(period * (PERIOD_MIN + INTERMISSION_MIN) + min) + Duration::seconds(sec)
+    }
+
+    fn format_time(time: DateTime<Utc>) -> String {
+        time.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Utc, TimeZone, Duration};
+    use tempdir::TempDir;
+
+    use crate::{api_teams_service::TeamsMap, models::{League, Season, GameType}, models_api::{game::ApiGame, report::GameStatus, event::{ApiGameEvent, ApiEventType, GoalInfo}}};
+
+    use super::FeedService;
+
+    #[test]
+    fn feed() {
+        let start = Utc.with_ymd_and_hms(2023, 9, 16, 16, 0, 0).unwrap();
+        let game = |uuid: &str, days: i64, status: GameStatus| ApiGame {
+            game_uuid: uuid.to_string(),
+            home_team_code: "LHF".to_string(),
+            away_team_code: "FHC".to_string(),
+            home_team_result: 2,
+            away_team_result: 1,
+            start_date_time: start + Duration::days(days),
+            status,
+            shootout: false,
+            overtime: false,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        };
+        let games = vec![
+            game("feed_game1", 0, GameStatus::Finished),
+            game("feed_game2", 2, GameStatus::Finished),
+            game("feed_game3", 4, GameStatus::Coming),
+        ];
+        let goal = ApiGameEvent {
+            game_uuid: "feed_game2".to_string(),
+            event_id: "7".to_string(),
+            revision: 1,
+            status: GameStatus::Period2,
+            gametime: "05:30".to_string(),
+            description: "".to_string(),
+            info: ApiEventType::Goal(GoalInfo { team: "FHC".to_string(), player: None, team_advantage: "EQ".to_string(), home_team_result: 0, away_team_result: 1, location: None }),
+        };
+        let events = HashMap::from([("feed_game2".to_string(), vec![goal])]);
+
+        let feed = FeedService::get("LHF & SHL", "team:LHF", &games, &events, &TeamsMap::new(), &[]);
+
+        assert!(feed.contains("<title>LHF &amp; SHL</title>"));
+        assert_eq!(feed.matches("<entry>").count(), 3);
+        assert!(!feed.contains("feed_game3"));
+        assert!(feed.contains("<updated>2023-09-18T18:30:00Z</updated>\n<summary>LHF 2 - 1 FHC</summary>"));
+        assert!(feed.contains("<title>LHF vann</title>"));
+        assert!(feed.contains("<title>Mål för FHC</title>\n<updated>2023-09-18T16:43:30Z</updated>"));
+        // newest first
+        assert!(feed.find("feed_game2:result").unwrap() < feed.find("feed_game2:7").unwrap());
+        assert!(feed.find("feed_game2:7").unwrap() < feed.find("feed_game1:result").unwrap());
+    }
+
+    #[test]
+    fn cached_until_invalidated() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let game = |uuid: &str| ApiGame {
+            game_uuid: uuid.to_string(),
+            home_team_code: "FEED1".to_string(),
+            away_team_code: "FEED2".to_string(),
+            home_team_result: 2,
+            away_team_result: 1,
+            start_date_time: Utc.with_ymd_and_hms(1995, 9, 16, 16, 0, 0).unwrap(),
+            status: GameStatus::Finished,
+            shootout: false,
+            overtime: false,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(1995),
+            gametime: None,
+            votes: None,
+        };
+        let read = |games: Vec<ApiGame>| FeedService::read_cached("FEED1", "team:FEED1", games, &TeamsMap::new(), &[]);
+        assert!(std::str::from_utf8(&read(vec![game("feed_cached1")]).body).unwrap().contains("feed_cached1"));
+
+        assert!(!std::str::from_utf8(&read(vec![game("feed_cached2")]).body).unwrap().contains("feed_cached2"));
+        FeedService::invalidate();
+        assert!(std::str::from_utf8(&read(vec![game("feed_cached2")]).body).unwrap().contains("feed_cached2"));
+    }
+}
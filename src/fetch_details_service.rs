@@ -23,7 +23,9 @@ impl FetchDetailsService {
         if !db.is_stale(&"key".to_string(), Some(Duration::from_secs(60 * 60))) {
             return;
         }
-        let all_games = ApiSeasonService::read_all();
+        let all_games: Vec<ApiGame> = Season::get_all().iter()
+            .flat_map(ApiSeasonService::read)
+            .collect();
         let mut applicable_games: Vec<&ApiGame> = all_games.iter()
             .filter(|e| e.played)
             .filter(|e| 
@@ -41,7 +43,7 @@ impl FetchDetailsService {
             futures::join!(
                 StatsService::update(&e.league, &e.season, &e.game_uuid, Some(e.season.clone().into())),
                 PlayerService::update(&e.league, &e.game_uuid, Some(e.season.clone().into())),
-                EventService::update(&e.season, &e.game_uuid, Some(e.season.clone().into()))
+                EventService::update(&e.league, &e.season, &e.game_uuid, Some(e.season.clone().into()))
             );
             
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use crate::{models::GameType, models_api::{game::ApiGame, form::{TeamForm, FormGame}, standings::{GameResult, StandingSplit, TeamCode}}};
+
+pub struct FormService;
+impl FormService {
+
+    /**
+     * Form over each team's last nr_games played games, ordered by league and team
+     */
+    pub fn get(games: &[ApiGame], nr_games: usize) -> Vec<TeamForm> {
+        let mut played: Vec<&ApiGame> = games.iter().filter(|e| e.played).collect();
+        played.sort_by_key(|e| std::cmp::Reverse(e.start_date_time));
+
+        let mut games_per_team: BTreeMap<&TeamCode, Vec<&ApiGame>> = BTreeMap::new();
+        for game in played {
+            games_per_team.entry(&game.home_team_code).or_default().push(game);
+            games_per_team.entry(&game.away_team_code).or_default().push(game);
+        }
+        let mut result: Vec<TeamForm> = games_per_team.into_iter()
+            .map(|(team_code, games)| FormService::get_team_form(team_code, &games, nr_games))
+            .collect();
+        result.sort_by(|a, b| a.league.cmp(&b.league).then_with(|| a.team_code.cmp(&b.team_code)));
+        result
+    }
+
+    /**
+     * Games ordered newest first, never empty
+     */
+    fn get_team_form(team_code: &str, games: &[&ApiGame], nr_games: usize) -> TeamForm {
+        let results: Vec<GameResult> = games.iter().map(|e| e.get_result_for(team_code)).collect();
+        let streak = |f: fn(&GameResult) -> bool| results.iter().take_while(|e| f(e)).count() as u16;
+
+        let mut total = StandingSplit::default();
+        let mut home = StandingSplit::default();
+        let mut away = StandingSplit::default();
+        let mut last = vec![];
+        for (game, result) in games.iter().zip(results.iter()).take(nr_games) {
+            let (gf, ga) = game.get_goals_for(team_code);
+            let points = game.get_points_for(team_code);
+            let is_home = game.home_team_code == team_code;
+            total.add_game(result, gf, ga, points);
+            match is_home {
+                true => home.add_game(result, gf, ga, points),
+                false => away.add_game(result, gf, ga, points),
+            }
+            last.push(FormGame {
+                game_uuid: game.game_uuid.clone(),
+                start_date_time: game.start_date_time,
+                opponent: match is_home {
+                    true => game.away_team_code.clone(),
+                    false => game.home_team_code.clone(),
+                },
+                is_home,
+                result: result.clone(),
+                gf,
+                ga,
+            });
+        }
+
+        let league = games.iter()
+            .find(|e| e.game_type == GameType::Season)
+            .unwrap_or(&games[0]) // a team is only known from its games
+            .league.clone();
+        TeamForm {
+            team_code: team_code.to_string(),
+            league,
+            last,
+            win_streak: streak(|e| matches!(e, GameResult::W | GameResult::OTW)),
+            loss_streak: streak(|e| matches!(e, GameResult::L | GameResult::OTL)),
+            point_streak: streak(|e| *e != GameResult::L),
+            total,
+            home,
+            away,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Utc, TimeZone, Duration};
+
+    use crate::{models::{League, Season, GameType}, models_api::{game::ApiGame, report::GameStatus, standings::GameResult}};
+
+    use super::FormService;
+
+    #[test]
+    fn form() {
+        let start = Utc.with_ymd_and_hms(2023, 9, 16, 16, 0, 0).unwrap();
+        let game = |i: i64, home: &str, away: &str, home_result: i16, away_result: i16, overtime: bool| ApiGame {
+            game_uuid: format!("game{i}"),
+            home_team_code: home.to_string(),
+            away_team_code: away.to_string(),
+            home_team_result: home_result,
+            away_team_result: away_result,
+            start_date_time: start + Duration::days(i),
+            status: GameStatus::Finished,
+            shootout: false,
+            overtime,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        };
+        let mut coming = game(9, "LHF", "FHC", 0, 0, false);
+        coming.played = false;
+        coming.status = GameStatus::Coming;
+        let games = vec![
+            game(0, "LHF", "FHC", 1, 4, false),
+            game(1, "FHC", "LHF", 2, 3, true),
+            game(2, "LHF", "TIK", 5, 1, false),
+            game(3, "TIK", "LHF", 2, 1, true),
+            game(4, "LHF", "FHC", 2, 0, false),
+            coming,
+        ];
+
+        let form = FormService::get(&games, 3);
+
+        assert_eq!(form.iter().map(|e| e.team_code.as_str()).collect::<Vec<&str>>(), vec!["FHC", "LHF", "TIK"]);
+        let lhf = &form[1];
+        let results: Vec<GameResult> = lhf.last.iter().map(|e| e.result.clone()).collect();
+        assert_eq!(results, vec![GameResult::W, GameResult::OTL, GameResult::W]);
+        assert_eq!(lhf.last[0].opponent, "FHC");
+        assert_eq!((lhf.win_streak, lhf.loss_streak, lhf.point_streak), (1, 0, 4));
+        assert_eq!((lhf.total.gp, lhf.total.points, lhf.total.gf, lhf.total.ga), (3, 7, 8, 3));
+        assert_eq!((lhf.home.gp, lhf.home.w, lhf.away.gp, lhf.away.ot_l), (2, 2, 1, 1));
+
+        let fhc = &form[0];
+        assert_eq!((fhc.win_streak, fhc.loss_streak, fhc.point_streak), (0, 2, 0));
+        assert_eq!(fhc.last.len(), 3);
+    }
+}
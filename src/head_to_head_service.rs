@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use crate::{api_season_service::ApiSeasonService, models_api::{game::ApiGame, head_to_head::HeadToHead, standings::StandingSplit}, response_cache::{ResponseCache, CachedResponse}};
+
+pub struct HeadToHeadService;
+impl HeadToHeadService {
+
+    /**
+     * Every played meeting between the teams in the given games, regardless of season and game type
+     */
+    pub fn get(team1: &str, team2: &str, games: &[ApiGame]) -> HeadToHead {
+        let mut meetings: Vec<&ApiGame> = games.iter()
+            .filter(|e| e.played)
+            .filter(|e| (e.home_team_code == team1 && e.away_team_code == team2) || (e.home_team_code == team2 && e.away_team_code == team1))
+            .collect();
+        meetings.sort_by_key(|e| std::cmp::Reverse(e.start_date_time));
+
+        let mut team1_record = StandingSplit::default();
+        let mut team2_record = StandingSplit::default();
+        for game in &meetings {
+            for (team, record) in [(team1, &mut team1_record), (team2, &mut team2_record)] {
+                let (gf, ga) = game.get_goals_for(team);
+                record.add_game(&game.get_result_for(team), gf, ga, game.get_points_for(team));
+            }
+        }
+        HeadToHead {
+            team1: team1.to_string(),
+            team2: team2.to_string(),
+            team1_record,
+            team2_record,
+            nr_overtime: meetings.iter().filter(|e| e.overtime && !e.shootout).count() as u16,
+            nr_shootout: meetings.iter().filter(|e| e.shootout).count() as u16,
+            last_meeting: meetings.first().map(|e| (*e).clone()),
+            games: meetings.into_iter().cloned().collect(),
+        }
+    }
+
+    /**
+     * Every season read once per team pair, until the season games change
+     */
+    pub fn read_cached(team1: &str, team2: &str) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("h2h/{team1}/{team2}"), || {
+            let head_to_head = HeadToHeadService::get(team1, team2, &ApiSeasonService::read_all());
+            (serde_json::to_string(&head_to_head).unwrap_or_default(), None)
+        })
+    }
+
+    /**
+     * Summary of the cached head to head, without the games
+     */
+    pub fn read_summary(team1: &str, team2: &str) -> Option<HeadToHead> {
+        let mut head_to_head: HeadToHead = serde_json::from_slice(&HeadToHeadService::read_cached(team1, team2).body).ok()?;
+        head_to_head.games.clear();
+        Some(head_to_head)
+    }
+
+    pub fn invalidate() {
+        ResponseCache::invalidate_prefix("h2h/");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Utc, TimeZone, Duration};
+
+    use tempdir::TempDir;
+
+    use crate::{db::Db, models::{League, Season, GameType}, models_api::{game::ApiGame, head_to_head::HeadToHead, report::GameStatus}};
+
+    use super::HeadToHeadService;
+
+    #[test]
+    fn head_to_head() {
+        let start = Utc.with_ymd_and_hms(2022, 9, 16, 16, 0, 0).unwrap();
+        let game = |i: i64, home: &str, away: &str, home_result: i16, away_result: i16, overtime: bool, shootout: bool| ApiGame {
+            game_uuid: format!("game{i}"),
+            home_team_code: home.to_string(),
+            away_team_code: away.to_string(),
+            home_team_result: home_result,
+            away_team_result: away_result,
+            start_date_time: start + Duration::days(i * 100),
+            status: GameStatus::Finished,
+            shootout,
+            overtime,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2022 + i as u16 / 4),
+            gametime: None,
+            votes: None,
+        };
+        let games = vec![
+            game(0, "LHF", "FHC", 1, 4, false, false),
+            game(1, "FHC", "LHF", 2, 3, true, false),
+            game(2, "LHF", "TIK", 5, 1, false, false),
+            game(5, "LHF", "FHC", 3, 2, true, true),
+            game(6, "FHC", "LHF", 2, 0, false, false),
+        ];
+
+        let h2h = HeadToHeadService::get("LHF", "FHC", &games);
+
+        assert_eq!(h2h.games.len(), 4);
+        assert_eq!(h2h.last_meeting.map(|e| e.game_uuid), Some("game6".to_string()));
+        assert_eq!((h2h.team1_record.w, h2h.team1_record.ot_w, h2h.team1_record.l, h2h.team1_record.points), (0, 2, 2, 4));
+        assert_eq!((h2h.team2_record.w, h2h.team2_record.ot_l, h2h.team2_record.gf, h2h.team2_record.ga), (2, 2, 10, 7));
+        assert_eq!((h2h.nr_overtime, h2h.nr_shootout), (1, 1));
+    }
+
+    #[test]
+    fn cached_until_invalidated() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let game = |game_uuid: &str| ApiGame {
+            game_uuid: game_uuid.to_string(),
+            home_team_code: "H2H1".to_string(),
+            away_team_code: "H2H2".to_string(),
+            home_team_result: 2,
+            away_team_result: 1,
+            start_date_time: Utc.with_ymd_and_hms(1995, 9, 16, 16, 0, 0).unwrap(),
+            status: GameStatus::Finished,
+            shootout: false,
+            overtime: false,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(1995),
+            gametime: None,
+            votes: None,
+        };
+        let db: Db<Season, Vec<ApiGame>> = Db::new("v2_season_decorated");
+        _ = db.write(&Season(1995), &vec![game("h2h_game1")]);
+        let read = || serde_json::from_slice::<HeadToHead>(&HeadToHeadService::read_cached("H2H1", "H2H2").body).unwrap();
+        assert_eq!(read().games.len(), 1);
+
+        _ = db.write(&Season(1995), &vec![game("h2h_game1"), game("h2h_game2")]);
+        assert_eq!(read().games.len(), 1);
+        HeadToHeadService::invalidate();
+        assert_eq!(read().games.len(), 2);
+
+        let summary = HeadToHeadService::read_summary("H2H1", "H2H2").unwrap();
+        assert!(summary.games.is_empty());
+        assert_eq!(summary.team1_record.w, 2);
+    }
+}
@@ -13,16 +13,20 @@ use models_api::vote::VotePerGame;
 use msg_bus::{Msg, MsgBus};
 use sse_client::SseMsg;
 use standing_service::StandingService;
+use odds_service::OddsService;
+use pickem_service::PickemService;
+use prediction_service::{PredictionService, SafePredictionService};
 use tokio::select;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::sync::mpsc::{Sender, Receiver};
 
-use models::Season;
+use models::{GameType, LeagueRegistry, PlayoffFormatRegistry, Season, SeasonRegistry};
 use vote_service::{VoteService, SafeVoteService};
 use crate::api_game_details::ApiGameDetailsService;
 use crate::api_season_service::ApiSeasonService;
+use crate::api_ws::WsMsg;
 use crate::fetch_details_service::FetchDetailsService;
-use crate::models_api::event::ApiEventTypeLevel;
+use crate::models_api::event::{ApiEventTypeLevel, ApiEventType};
 use crate::models_api::report::{ApiGameReport, GameStatus};
 use crate::models_external::event::LiveState;
 use crate::msg_bus::UpdateReport;
@@ -35,6 +39,7 @@ use crate::player_service::PlayerService;
 use crate::sse_client::SseClient;
 use crate::season_service::SeasonService;
 use crate::stats_service::StatsService;
+use crate::status_service::StatusService;
 use tracing::log;
 use crate::user_service::UserService;
 use lazy_static::lazy_static;
@@ -57,6 +62,16 @@ mod models_legacy;
 mod models_api;
 mod vote_service;
 mod standing_service;
+mod odds_service;
+mod pickem_service;
+mod pickem_group_service;
+mod prediction_service;
+mod form_service;
+mod head_to_head_service;
+mod response_cache;
+mod calendar_service;
+mod feed_service;
+mod export_service;
 mod api_teams_service;
 mod api;
 mod api_ws;
@@ -70,8 +85,6 @@ mod playoff_service;
 mod msg_bus;
 mod status_service;
 
-#[cfg(test)]
-mod mock_test;
 
 lazy_static! {
     pub static ref CONFIG: Config = config_handler::get_config();
@@ -98,6 +111,11 @@ async fn main() {
         .with_max_level(tracing::Level::INFO)
         .init();
 
+    SeasonRegistry::init(&CONFIG.seasons);
+    LeagueRegistry::init(&CONFIG.get_leagues());
+    PlayoffFormatRegistry::init(&CONFIG.playoff_formats);
+    log::info!("[SEASON] Current {}, registered {:?}", Season::get_current(), Season::get_all().iter().map(|e| e.to_string()).collect::<Vec<String>>());
+
     let (live_game_sender, live_game_receiver) = mpsc::channel(1000);
     let (poll_live_game_sender, poll_live_game_receiver) = mpsc::channel(1000);
     let (broadcast_sender, _) = tokio::sync::broadcast::channel(1000);
@@ -106,15 +124,19 @@ async fn main() {
 
     let api_season_service = ApiSeasonService::new();
     let vote_service = VoteService::new(vote_sender);
+    let prediction_service = PredictionService::new();
     for season in Season::get_all() {
         let (responses, _) = SeasonService { }.update(&season).await;
         let api_games = api_season_service.write().await.update(&season, &responses, vote_service.read().await.get_all());
         
         StandingService::update(&season, &api_games);
+        StandingService::update_history(&season, &api_games);
+        PlayoffService::update(&season, &api_games);
+        OddsService::update(&season, &api_games).await;
+        PickemService::update(&season, &api_games, &VoteService::read_votes(), &PredictionService::read_all());
     }
     let all_games = ApiSeasonService::read_all();
     ApiPlayerStatsService::update(&all_games);
-    PlayoffService::update(&Season::get_current(), &api_season_service.read().await.read_current_season());
 
     let notification_service = Arc::new(RwLock::new(NotificationService::new()));
     let msg_bus = Arc::new(MsgBus::new());
@@ -123,8 +145,9 @@ async fn main() {
         let api_season_service = api_season_service.clone();
         let broadcast_sender = broadcast_sender.clone();
         let vote_service = vote_service.clone();
+        let prediction_service = prediction_service.clone();
         let msg_bus = msg_bus.clone();
-        tokio::spawn(async { Api::serve(CONFIG.port, api_season_service, vote_service, broadcast_sender, msg_bus).await })
+        tokio::spawn(async { Api::serve(CONFIG.port, api_season_service, vote_service, prediction_service, broadcast_sender, msg_bus).await })
     };
     let h2 = {
         let api_season_service = api_season_service.clone();
@@ -141,7 +164,9 @@ async fn main() {
     let h4 = {
         let api_season_service = api_season_service.clone();
         let msg_bus = msg_bus.clone();
-        tokio::spawn(async { handle_stats_fetch(msg_bus, api_season_service).await })
+        let notification_service = notification_service.clone();
+        let prediction_service = prediction_service.clone();
+        tokio::spawn(async { handle_stats_fetch(msg_bus, api_season_service, notification_service, prediction_service).await })
     };
     let h5 = {
         let api_season_service = api_season_service.clone();
@@ -158,7 +183,13 @@ async fn main() {
         let msg_bus = msg_bus.clone();
         tokio::spawn(async { handle_poll_loop(api_season_service, poll_live_game_receiver, msg_bus).await; })
     };
-    join_all(vec!(h1, h2, h3, h4, h5, h6, h7)).await;
+    let h8 = {
+        let broadcast_sender = broadcast_sender.clone();
+        let msg_bus = msg_bus.clone();
+        let api_season_service = api_season_service.clone();
+        tokio::spawn(async { handle_ws_broadcast(msg_bus, broadcast_sender, api_season_service).await; })
+    };
+    join_all(vec!(h1, h2, h3, h4, h5, h6, h7, h8)).await;
 
 }
 
@@ -178,6 +209,7 @@ async fn handle_loop(
         let api_games = if updated {
             let api_games = api_season_service.write().await.update(&season, &responses, vote_service.read().await.get_all());
             StandingService::update(&season, &api_games);
+            OddsService::update(&season, &api_games).await;
             ApiPlayerStatsService::update(&api_games);
             api_games
         } else {
@@ -323,7 +355,7 @@ async fn handle_poll_loop(
                     if let Some(g) = api_season_service.read().await.read_current_season_game(&uuid) {
                         let (report_update, event_update) = futures::join!(
                             GameReportService::fetch_update(&g.league, &g.game_uuid, Some(Duration::from_millis(0))),
-                            rest_client::get_events_2023(&g.game_uuid),
+                            rest_client::get_events_2023(&g.league, &g.game_uuid),
                         );
                         if let Some(report) = report_update {
                             log::info!("[POLL] new report {report}");
@@ -381,9 +413,15 @@ async fn write_event_service(
                     GameReportService::store(&game_uuid, &report);
                     log::info!("[WRITE] REPORT {report}");
                     let updated_api_game = api_season_service.write().await.update_from_report(&report);
+                    let mut game_ended = false;
                     if let Some(g) = updated_api_game {
                         if let Some(report_event) = ReportStateMachine::process(&report, &old_report) {
                             notification_service.write().await.process(&g, &report_event).await;
+                            if matches!(report_event.info, ApiEventType::GameEnd(_)) {
+                                game_ended = true;
+                                let games = api_season_service.read().await.read_current_season();
+                                notification_service.write().await.process_vote_results(&g, &games).await;
+                            }
                         } else {
                             notification_service.write().await.process_live_activity(&g).await;
                         }
@@ -391,7 +429,10 @@ async fn write_event_service(
                         log::error!("[SSE] Notification error, no game found for {}", game_uuid);
                     }
         
-                    msg_bus.send(Msg::ReportUpdated { report, game_uuid });
+                    msg_bus.send(Msg::ReportUpdated { report, game_uuid: game_uuid.clone() });
+                    if game_ended {
+                        msg_bus.send(Msg::GameEnded { game_uuid });
+                    }
                 }   
             } else if let Msg::AddEvent { event, game_uuid } = msg {
                 if event.info.get_level() != ApiEventTypeLevel::Low {
@@ -400,18 +441,28 @@ async fn write_event_service(
                     } else {
                         log::error!("[SSE] Notification error, no game found for {}", game_uuid);
                     }
+                    api_season_service.write().await.update_from_event(&game_uuid);
                     msg_bus.send(Msg::EventUpdated { event: event.clone(), game_uuid });
                 }
+            } else if let Msg::PlayoffSeriesUpdated { event, game_uuid: _ } = msg {
+                notification_service.write().await.process_playoff_series(&event).await;
             }
         }
     }
 }
 
 
-async fn handle_stats_fetch(msg_bus: Arc<MsgBus>, api_season_service: SafeApiSeasonService) {
+async fn handle_stats_fetch(msg_bus: Arc<MsgBus>, api_season_service: SafeApiSeasonService, notification_service: Arc<RwLock<NotificationService>>, prediction_service: SafePredictionService) {
     let mut receiver = msg_bus.subscribe();
     loop {
         if let Ok(msg) = receiver.recv().await {
+            if let Msg::GameEnded { game_uuid } = &msg {
+                // predictions are scored on the ReportUpdated sent before
+                log::info!("Game {game_uuid} ended, update pickem");
+                let all_games = api_season_service.read().await.read_current_season();
+                PickemService::update(&Season::get_current(), &all_games, &VoteService::read_votes(), &PredictionService::read_all());
+                continue;
+            }
             let should_update = matches!(msg, Msg::EventUpdated { event: _, game_uuid: _ } | Msg::ReportUpdated { report: _, game_uuid: _ });
             if !should_update {
                 continue;
@@ -421,7 +472,15 @@ async fn handle_stats_fetch(msg_bus: Arc<MsgBus>, api_season_service: SafeApiSea
                 if g.status == GameStatus::Finished {
                     log::info!("Game {g} finished, update standings");
                     StandingService::update(&Season::get_current(), &all_games);
-                    PlayoffService::update(&g.season, &all_games);
+                    if let Some(standings) = StandingService::read(Season::get_current()) {
+                        notification_service.write().await.process_clinches(&Season::get_current(), &standings).await;
+                    }
+                    StandingService::add_snapshot(&Season::get_current(), &all_games, &g.game_uuid);
+                    for event in PlayoffService::update(&g.season, &all_games) {
+                        msg_bus.send(Msg::PlayoffSeriesUpdated { event, game_uuid: g.game_uuid.clone() });
+                    }
+                    OddsService::update(&g.season, &all_games).await;
+                    prediction_service.write().await.score_game(g, &EventService::read(&g.game_uuid));
                     ApiPlayerStatsService::update(&all_games);
                 }
                 StatsService::update(&g.league, &g.season, &g.game_uuid, Some(std::time::Duration::from_secs(30))).await;
@@ -431,6 +490,39 @@ async fn handle_stats_fetch(msg_bus: Arc<MsgBus>, api_season_service: SafeApiSea
     }
 }
 
+async fn handle_ws_broadcast(msg_bus: Arc<MsgBus>, broadcast_sender: broadcast::Sender<WsMsg>, api_season_service: SafeApiSeasonService) {
+    let mut receiver = msg_bus.subscribe();
+    let mut last_live_standings = None;
+    let mut last_status_ids: Vec<String> = StatusService::read_active(None, None).into_iter().map(|e| e.id).collect();
+    let mut status_interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        select! {
+            msg = receiver.recv() => if let Ok(Msg::ReportUpdated { report: _, game_uuid }) = msg {
+                let games = api_season_service.read().await.read_current_season();
+                let is_season_game = games.iter().any(|e| e.game_uuid == game_uuid && e.game_type == GameType::Season);
+                if is_season_game {
+                    // only when the projected table changed, not on every clock update
+                    let live_standings = Some(StandingService::get_live(&games));
+                    if live_standings != last_live_standings {
+                        _ = broadcast_sender.send(live_standings.clone().unwrap().into());
+                        last_live_standings = live_standings;
+                    }
+                }
+            },
+            _ = status_interval.tick() => {
+                // statuses may have started or expired since they were last broadcast
+                let statuses = StatusService::read_active(None, None);
+                let status_ids: Vec<String> = statuses.iter().map(|e| e.id.clone()).collect();
+                if status_ids != last_status_ids {
+                    log::info!("[WS] Active statuses changed {:?}", status_ids);
+                    _ = broadcast_sender.send(statuses.into());
+                    last_status_ids = status_ids;
+                }
+            }
+        }
+    }
+}
+
 async fn handle_votes(api_season_service: SafeApiSeasonService, mut vote_receiver: Receiver<(String, VotePerGame)>) {
     loop {
         if let Some((game_uuid, votes_per_game)) = vote_receiver.recv().await {
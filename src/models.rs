@@ -1,15 +1,104 @@
+use chrono::{NaiveDate, Utc};
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::str::FromStr;
+use std::sync::RwLock;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(from = "String", into = "String")]
+pub struct League(Cow<'static, str>);
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub enum League {
-    SHL,
-    HA,
-}
 impl League {
+    pub const SHL: League = League(Cow::Borrowed("SHL"));
+    pub const HA: League = League(Cow::Borrowed("HA"));
+
     pub fn get_all() -> Vec<League> {
-        vec![League::SHL, League::HA]
+        LeagueRegistry::get_all().into_iter().map(|e| e.code).collect()
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl From<String> for League {
+    fn from(value: String) -> Self {
+        League(Cow::Owned(value))
+    }
+}
+impl From<League> for String {
+    fn from(value: League) -> Self {
+        value.0.into_owned()
+    }
+}
+impl FromStr for League {
+    type Err = ParseStringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let league = League::from(s.to_string());
+        match LeagueRegistry::get(&league) {
+            Some(_) => Ok(league),
+            None => Err(ParseStringError),
+        }
+    }
+}
+impl Display for League {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::fmt::Debug for League {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeagueInfo {
+    pub code: League,
+    pub uuid: String, // upstream seriesUuid
+    #[serde(default)]
+    pub url: String,
+    #[serde(default = "GameType::get_all")]
+    pub game_types: Vec<GameType>,
+}
+
+impl LeagueInfo {
+    pub fn new(code: League, uuid: &str, url: &str) -> LeagueInfo {
+        LeagueInfo { code, uuid: uuid.to_string(), url: url.to_string(), game_types: GameType::get_all() }
+    }
+}
+
+pub fn default_leagues(shl_url: &str, ha_url: &str) -> Vec<LeagueInfo> {
+    vec![
+        LeagueInfo::new(League::SHL, "qQ9-bb0bzEWUk", shl_url),
+        LeagueInfo::new(League::HA, "qQ9-594cW8OWD", ha_url),
+    ]
+}
+
+lazy_static! {
+    static ref LEAGUES: RwLock<Vec<LeagueInfo>> = RwLock::new(default_leagues("", ""));
+}
+
+pub struct LeagueRegistry;
+impl LeagueRegistry {
+    pub fn init(leagues: &[LeagueInfo]) {
+        if leagues.is_empty() {
+            return
+        }
+        *LEAGUES.write().unwrap() = leagues.to_vec();
+    }
+
+    pub fn get_all() -> Vec<LeagueInfo> {
+        LEAGUES.read().unwrap().clone()
+    }
+
+    pub fn get(league: &League) -> Option<LeagueInfo> {
+        LEAGUES.read().unwrap().iter().find(|e| &e.code == league).cloned()
+    }
+
+    pub fn get_url(league: &League) -> String {
+        LeagueRegistry::get(league).map(|e| e.url).unwrap_or_default()
     }
 }
 
@@ -39,51 +128,129 @@ impl FromStr for GameType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
-pub enum Season {
-    Season2023,
-    Season2022,
-    Season2021,
-    Season2020,
-    Season2019,
-    Season2018,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SeasonInfo {
+    pub year: u16,
+    pub uuid: String,
+    pub start_date: NaiveDate, // from this date the season is considered current
+
+    #[serde(default)]
+    pub legacy_api: bool, // events and stats are fetched from the pre 2023 endpoints
+}
+
+impl SeasonInfo {
+    fn new(year: u16, uuid: &str, legacy_api: bool) -> SeasonInfo {
+        SeasonInfo {
+            year,
+            uuid: uuid.to_string(),
+            start_date: NaiveDate::from_ymd_opt(i32::from(year), 8, 1).unwrap(),
+            legacy_api,
+        }
+    }
+}
+
+fn default_seasons() -> Vec<SeasonInfo> {
+    vec![
+        SeasonInfo::new(2018, "qUv-YXiuQN45", true),
+        SeasonInfo::new(2019, "qWX-334j11U5o1", true),
+        SeasonInfo::new(2020, "qY7-AdVh5z1XJ", true),
+        SeasonInfo::new(2021, "qZl-8qa6OaFXf", true),
+        SeasonInfo::new(2022, "qbN-XMFfjGVt", true),
+        SeasonInfo::new(2023, "qcz-3NvSZ2Cmh", false),
+    ]
+}
+
+lazy_static! {
+    static ref SEASONS: RwLock<Vec<SeasonInfo>> = RwLock::new(default_seasons());
 }
 
+pub struct SeasonRegistry;
+impl SeasonRegistry {
+    /**
+     * Replaces the built in seasons, an empty list keeps the defaults
+     */
+    pub fn init(seasons: &[SeasonInfo]) {
+        if seasons.is_empty() {
+            return
+        }
+        let mut seasons = seasons.to_vec();
+        seasons.sort_by_key(|e| e.year);
+        *SEASONS.write().unwrap() = seasons;
+    }
+
+    pub fn get_all() -> Vec<SeasonInfo> {
+        SEASONS.read().unwrap().clone()
+    }
+
+    pub fn get(season: &Season) -> Option<SeasonInfo> {
+        SEASONS.read().unwrap().iter().find(|e| e.year == season.0).cloned()
+    }
+
+    #[allow(dead_code)] // used by the integration tests
+    pub fn from_uuid(uuid: &str) -> Option<Season> {
+        SEASONS.read().unwrap().iter().find(|e| e.uuid == uuid).map(|e| Season(e.year))
+    }
+
+    fn get_current_at(date: NaiveDate) -> Season {
+        let seasons = SEASONS.read().unwrap();
+        seasons.iter()
+            .rfind(|e| e.start_date <= date)
+            .or(seasons.first())
+            .map(|e| Season(e.year))
+            .expect("[SEASON] No seasons registered")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct Season(pub u16);
+
 impl Season {
     pub fn get_current() -> Season {
-        Season::Season2023
+        SeasonRegistry::get_current_at(Utc::now().date_naive())
     }
     pub fn is_current(&self) -> bool {
         self == &Season::get_current()
     }
     pub fn get_all() -> Vec<Season> {
-        vec![Season::Season2018, Season::Season2019, Season::Season2020, Season::Season2021, Season::Season2022, Season::Season2023]
+        SeasonRegistry::get_all().into_iter().map(|e| Season(e.year)).collect()
+    }
+    pub fn get_uuid(&self) -> String {
+        SeasonRegistry::get(self).map(|e| e.uuid).unwrap_or_default()
+    }
+    pub fn is_legacy_api(&self) -> bool {
+        SeasonRegistry::get(self).map(|e| e.legacy_api).unwrap_or(false)
+    }
+
+    fn parse_year(s: &str) -> Option<Season> {
+        s.strip_prefix("Season").unwrap_or(s).parse::<u16>().ok().map(Season)
     }
 }
 impl FromStr for Season {
     type Err = ParseStringError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "2023" => Ok(Season::Season2023),
-            "Season2023" => Ok(Season::Season2023),
-            "2022" => Ok(Season::Season2022),
-            "Season2022" => Ok(Season::Season2022),
-            "2021" => Ok(Season::Season2021),
-            "Season2021" => Ok(Season::Season2021),
-            "2020" => Ok(Season::Season2020),
-            "Season2020" => Ok(Season::Season2020),
-            "2019" => Ok(Season::Season2019),
-            "Season2019" => Ok(Season::Season2019),
-            "2018" => Ok(Season::Season2018),
-            "Season2018" => Ok(Season::Season2018),
-            _ => Err(ParseStringError)
+        match Season::parse_year(s) {
+            Some(season) if SeasonRegistry::get(&season).is_some() => Ok(season),
+            _ => Err(ParseStringError),
         }
     }
 }
+impl TryFrom<String> for Season {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Season::parse_year(&value).ok_or_else(|| format!("invalid season {value}"))
+    }
+}
+impl From<Season> for String {
+    fn from(value: Season) -> Self {
+        value.to_string()
+    }
+}
 impl Display for Season {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "Season{}", self.0)
     }
 }
 
@@ -104,7 +271,7 @@ impl StringOrNum {
         match self {
             StringOrNum::String(str) => str.parse::<i16>().unwrap_or(0),
             StringOrNum::Number(n) => *n,
-            StringOrNum::Arr(a) => a.get(0).map(|e| e.parse::<i16>().ok()).unwrap_or(None).unwrap_or(0),
+            StringOrNum::Arr(a) => a.first().map(|e| e.parse::<i16>().ok()).unwrap_or(None).unwrap_or(0),
         }
     }
 
@@ -117,11 +284,195 @@ impl StringOrNum {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayoffRoundName {
+    Eight,
+    Quarter,
+    Semi,
+    Final,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayoffRound {
+    pub name: PlayoffRoundName,
+    pub best_of: u8,
+    #[serde(default)]
+    pub seeds: Vec<u8>, // regular season ranks entering directly in this round, a first round of seeds only is a play-in
+    #[serde(default = "default_reseed")]
+    pub reseed: bool, // the best remaining seed meets the worst, instead of a fixed bracket
+}
+
+fn default_reseed() -> bool {
+    true
+}
+
+impl PlayoffRound {
+    fn new(name: PlayoffRoundName, best_of: u8, seeds: Vec<u8>) -> PlayoffRound {
+        PlayoffRound { name, best_of, seeds, reseed: true }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayoffSeed {
+    Rank(u8),
+    FinalLoser(League), // qualification against the losing finalist of another league
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DemotionFormat {
+    pub best_of: u8,
+    pub team1: PlayoffSeed,
+    pub team2: PlayoffSeed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayoffFormat {
+    pub league: League,
+    pub from_season: u16, // applies from this season until a later format of the league
+    pub rounds: Vec<PlayoffRound>, // in order, empty when no playoffs are played
+    #[serde(default)]
+    pub demotion: Option<DemotionFormat>,
+}
+
+impl PlayoffFormat {
+    /**
+     * Ranks entering a round after the first one, or the first round when no later round has seeds
+     */
+    pub fn get_direct_seeds(&self) -> Vec<u8> {
+        let later_seeds: Vec<u8> = self.rounds.iter().skip(1).flat_map(|e| e.seeds.clone()).collect();
+        match later_seeds.is_empty() {
+            true => self.rounds.first().map(|e| e.seeds.clone()).unwrap_or_default(),
+            false => later_seeds,
+        }
+    }
+
+    /**
+     * The first round is a play-in when later rounds also have seeded teams
+     */
+    pub fn get_play_in_seeds(&self) -> Vec<u8> {
+        let has_later_seeds = self.rounds.iter().skip(1).any(|e| !e.seeds.is_empty());
+        match has_later_seeds {
+            true => self.rounds.first().map(|e| e.seeds.clone()).unwrap_or_default(),
+            false => vec![],
+        }
+    }
+
+    pub fn get_demotion_ranks(&self) -> Vec<u8> {
+        self.demotion.iter()
+            .flat_map(|e| [&e.team1, &e.team2])
+            .filter_map(|e| match e {
+                PlayoffSeed::Rank(rank) => Some(*rank),
+                PlayoffSeed::FinalLoser(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn default_playoff_formats() -> Vec<PlayoffFormat> {
+    use PlayoffRoundName::*;
+    let rounds = vec![
+        PlayoffRound::new(Eight, 3, vec![7, 8, 9, 10]),
+        PlayoffRound::new(Quarter, 7, vec![1, 2, 3, 4, 5, 6]),
+        PlayoffRound::new(Semi, 7, vec![]),
+        PlayoffRound::new(Final, 7, vec![]),
+    ];
+    let ha_rounds = vec![
+        PlayoffRound::new(Eight, 3, vec![4, 5, 6, 7]),
+        PlayoffRound::new(Quarter, 5, vec![2, 3]),
+        PlayoffRound::new(Semi, 7, vec![]),
+        PlayoffRound::new(Final, 7, vec![1]),
+    ];
+    vec![
+        PlayoffFormat { league: League::SHL, from_season: 2018, rounds: rounds.clone(), demotion: Some(DemotionFormat { best_of: 7, team1: PlayoffSeed::Rank(13), team2: PlayoffSeed::Rank(14) }) },
+        // playoffs cancelled due to covid
+        PlayoffFormat { league: League::SHL, from_season: 2019, rounds: vec![], demotion: None },
+        PlayoffFormat { league: League::SHL, from_season: 2020, rounds: rounds.clone(), demotion: Some(DemotionFormat { best_of: 7, team1: PlayoffSeed::Rank(13), team2: PlayoffSeed::Rank(14) }) },
+        // the last team is relegated directly, the second last meets the losing HA finalist
+        PlayoffFormat { league: League::SHL, from_season: 2021, rounds, demotion: Some(DemotionFormat { best_of: 7, team1: PlayoffSeed::Rank(13), team2: PlayoffSeed::FinalLoser(League::HA) }) },
+
+        // the series winner goes directly to the final and meets the winner of the playoffs
+        PlayoffFormat { league: League::HA, from_season: 2018, rounds: ha_rounds.clone(), demotion: None },
+        PlayoffFormat { league: League::HA, from_season: 2019, rounds: vec![], demotion: None },
+        PlayoffFormat { league: League::HA, from_season: 2020, rounds: ha_rounds, demotion: None },
+    ]
+}
+
+lazy_static! {
+    static ref PLAYOFF_FORMATS: RwLock<Vec<PlayoffFormat>> = RwLock::new(default_playoff_formats());
+}
+
+pub struct PlayoffFormatRegistry;
+impl PlayoffFormatRegistry {
+    /**
+     * Replaces the built in formats, an empty list keeps the defaults
+     */
+    pub fn init(formats: &[PlayoffFormat]) {
+        if formats.is_empty() {
+            return
+        }
+        *PLAYOFF_FORMATS.write().unwrap() = formats.to_vec();
+    }
+
+    pub fn get(league: &League, season: &Season) -> Option<PlayoffFormat> {
+        PLAYOFF_FORMATS.read().unwrap().iter()
+            .filter(|e| &e.league == league && e.from_season <= season.0)
+            .max_by_key(|e| e.from_season)
+            .cloned()
+    }
+}
+
 #[derive(Clone)]
 pub struct SeasonKey(pub Season, pub League, pub GameType);
 
 impl std::fmt::Display for SeasonKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}/{:?}/{:?}", self.0, self.1, self.2)
+        write!(f, "{}/{:?}/{:?}", self.0, self.1, self.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Season, SeasonRegistry, PlayoffFormatRegistry, PlayoffRound, PlayoffRoundName, League};
+
+    #[test]
+    fn playoff_format_by_season() {
+        let format = PlayoffFormatRegistry::get(&League::SHL, &Season(2019)).unwrap();
+        assert!(format.rounds.is_empty());
+        let format = PlayoffFormatRegistry::get(&League::SHL, &Season(2023)).unwrap();
+        assert_eq!(format.from_season, 2021);
+        assert_eq!(format.rounds.len(), 4);
+        let format = PlayoffFormatRegistry::get(&League::HA, &Season(2022)).unwrap();
+        assert_eq!(format.rounds[3].seeds, vec![1]);
+        assert!(PlayoffFormatRegistry::get(&League::HA, &Season(2017)).is_none());
+    }
+
+    #[test]
+    fn playoff_round_reseeds_by_default() {
+        let round: PlayoffRound = serde_json::from_str(r#"{ "name": "quarter", "best_of": 7 }"#).unwrap();
+        assert_eq!(round, PlayoffRound::new(PlayoffRoundName::Quarter, 7, vec![]));
+        let round: PlayoffRound = serde_json::from_str(r#"{ "name": "quarter", "best_of": 7, "reseed": false }"#).unwrap();
+        assert!(!round.reseed);
+    }
+
+    #[test]
+    fn current_season_by_date() {
+        assert_eq!(SeasonRegistry::get_current_at(NaiveDate::from_ymd_opt(2022, 3, 1).unwrap()), Season(2021));
+        assert_eq!(SeasonRegistry::get_current_at(NaiveDate::from_ymd_opt(2022, 9, 1).unwrap()), Season(2022));
+        assert_eq!(SeasonRegistry::get_current_at(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()), Season(2023));
+        assert_eq!(SeasonRegistry::get_current_at(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()), Season(2018));
+    }
+
+    #[test]
+    fn parse_season() {
+        assert_eq!("2022".parse::<Season>(), Ok(Season(2022)));
+        assert_eq!("Season2022".parse::<Season>(), Ok(Season(2022)));
+        assert!("2017".parse::<Season>().is_err());
+        assert_eq!(serde_json::to_string(&Season(2022)).unwrap(), "\"Season2022\"");
+        assert_eq!(serde_json::from_str::<Season>("\"Season2022\"").unwrap(), Season(2022));
+        assert_eq!(Season(2022).get_uuid(), "qbN-XMFfjGVt");
     }
 }
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::models::League;
+
+use super::standings::{TeamCode, GameResult, StandingSplit};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FormGame {
+    pub game_uuid: String,
+    pub start_date_time: DateTime<Utc>,
+    pub opponent: TeamCode,
+    pub is_home: bool,
+    pub result: GameResult,
+    pub gf: u16,
+    pub ga: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TeamForm {
+    pub team_code: TeamCode,
+    pub league: League,
+    pub last: Vec<FormGame>, // newest first
+    // streaks run over the whole season, not only the window
+    pub win_streak: u16,
+    pub loss_streak: u16,
+    pub point_streak: u16,
+    // over the last games only
+    pub total: StandingSplit,
+    pub home: StandingSplit,
+    pub away: StandingSplit,
+}
@@ -7,7 +7,7 @@ use crate::models::{GameType, League, Season};
 
 use super::{report::GameStatus, vote::ApiVotePerGame};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ApiGame {
     pub game_uuid: String,
     pub home_team_code: String,
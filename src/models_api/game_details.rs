@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-use super::{game::ApiGame, event::ApiGameEvent, stats::ApiGameStats, athlete::ApiAthlete};
+use super::{game::ApiGame, event::ApiGameEvent, stats::ApiGameStats, athlete::ApiAthlete, head_to_head::HeadToHead};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ApiGameDetails {
@@ -8,4 +8,6 @@ pub struct ApiGameDetails {
     pub stats: Option<ApiGameStats>,
     pub game: ApiGame,
     pub players: Vec<ApiAthlete>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub head_to_head: Option<HeadToHead>, // upcoming games only
 }
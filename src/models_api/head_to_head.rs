@@ -0,0 +1,16 @@
+use serde::{Serialize, Deserialize};
+
+use super::{game::ApiGame, standings::{TeamCode, StandingSplit}};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeadToHead {
+    pub team1: TeamCode,
+    pub team2: TeamCode,
+    pub team1_record: StandingSplit,
+    pub team2_record: StandingSplit,
+    pub nr_overtime: u16, // decided in overtime, shootouts not included
+    pub nr_shootout: u16,
+    pub last_meeting: Option<ApiGame>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub games: Vec<ApiGame>, // newest first, left out of the summary in game details
+}
@@ -8,4 +8,9 @@ pub mod standings;
 pub mod user;
 pub mod live_activity;
 pub mod vote;
-pub mod update_report;
\ No newline at end of file
+pub mod update_report;
+pub mod odds;
+pub mod pickem;
+pub mod form;
+pub mod head_to_head;
+pub mod sync;
@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Deserialize};
+
+use crate::models::League;
+
+use super::standings::TeamCode;
+
+/**
+ * Serialized as { "SHL": [...], "HA": [...] }, one entry per league
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Odds(pub BTreeMap<League, Vec<TeamOdds>>);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamOdds {
+    pub team_code: TeamCode,
+    pub ranks: Vec<f32>, // probability of each final rank, first place first
+    pub playoff: f32, // any playoff spot, play-in included
+    pub direct_playoff: f32,
+    pub play_in: f32,
+    pub demotion: f32,
+}
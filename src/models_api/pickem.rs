@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+
+use crate::models::League;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PickemVote {
+    pub game_uuid: String,
+    pub team_code: String,
+    pub home_team_code: String,
+    pub away_team_code: String,
+    pub start_date_time: DateTime<Utc>,
+    pub league: League,
+    pub correct: Option<bool>, // None until the game is finished
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PickemUserStats {
+    pub user_id: String,
+    pub rank: u32,
+    pub points: u32,
+    pub nr_votes: u32, // on finished games
+    pub nr_correct: u32,
+    pub accuracy: f32,
+    pub current_streak: u16,
+    pub longest_streak: u16,
+    #[serde(default)]
+    pub nr_predictions: u32, // on finished games
+    #[serde(default)]
+    pub prediction_points: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PickemLeaderboards {
+    pub season: Vec<PickemUserStats>,
+    pub weeks: BTreeMap<String, Vec<PickemUserStats>>, // keyed by ISO week, e.g. 2023-W42
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PickemUserHistory {
+    pub user_id: String,
+    pub season: Option<PickemUserStats>,
+    pub votes: Vec<PickemVote>, // newest first
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PickemGroup {
+    pub invite_code: String,
+    pub name: String,
+    pub owner_id: String,
+    pub members: Vec<String>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreatePickemGroup {
+    pub user_id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PickemGroupMember {
+    pub user_id: String,
+    pub invite_code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PredictionKind {
+    ExactScore { home_team_result: i16, away_team_result: i16 },
+    FirstGoalScorer {
+        player_id: i32,
+        #[serde(default)]
+        team_code: String, // set from the roster
+        #[serde(default)]
+        jersey: i32, // set from the roster, goal events only carry the jersey
+    },
+    Overtime { overtime: bool }, // overtime or shootout
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub user_id: String,
+    pub game_uuid: String,
+    pub kind: PredictionKind,
+    pub correct: Option<bool>, // None until the game is finished
+    #[serde(default)]
+    pub points: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PredictionBody {
+    pub game_uuid: String,
+    pub user_id: String,
+    pub kind: PredictionKind,
+}
@@ -1,16 +1,59 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 
 use crate::models::League;
 
 pub type TeamCode = String;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Standings {
-    pub SHL: Vec<Standing>,
-    pub HA: Vec<Standing>,
+/**
+ * Serialized as { "SHL": [...], "HA": [...] }, one entry per league
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Standings(pub BTreeMap<League, Vec<Standing>>);
+
+impl Standings {
+    pub fn get(&self, league: &League) -> &[Standing] {
+        self.0.get(league).map(|e| e.as_slice()).unwrap_or_default()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum GameResult {
+    W,
+    OTW, // overtime or shootout win
+    OTL, // overtime or shootout loss
+    L,
+}
+
+/**
+ * Mathematically decided outcomes of the regular season, strongest first
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Clinch {
+    FirstPlace,
+    DirectPlayoff, // top six in SHL
+    Playoff, // play-in included
+    Demotion, // also eliminated, but the stronger news
+    Eliminated,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StandingSplit {
+    pub gp: u16,
+    pub w: u16,
+    pub ot_w: u16,
+    pub ot_l: u16,
+    pub l: u16,
+    pub gf: u16,
+    pub ga: u16,
+    pub points: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Standing {
     pub team_code: TeamCode,
     pub rank: u8,
@@ -19,4 +62,104 @@ pub struct Standing {
     pub points: u16,
     pub diff: i16,
     pub league: League,
+
+    #[serde(default)]
+    pub w: u16,
+    #[serde(default)]
+    pub l: u16,
+    #[serde(default)]
+    pub reg_w: u16,
+    #[serde(default)]
+    pub ot_w: u16,
+    #[serde(default)]
+    pub so_w: u16,
+    #[serde(default)]
+    pub reg_l: u16,
+    #[serde(default)]
+    pub ot_l: u16,
+    #[serde(default)]
+    pub so_l: u16,
+    #[serde(default)]
+    pub gf: u16,
+    #[serde(default)]
+    pub ga: u16,
+
+    #[serde(default)]
+    pub home: StandingSplit,
+    #[serde(default)]
+    pub away: StandingSplit,
+
+    #[serde(default)]
+    pub last_five: Vec<GameResult>, // oldest first
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub clinched: Vec<Clinch>,
+}
+
+impl Standing {
+    pub fn new(team_code: &str, league: &League) -> Standing {
+        Standing {
+            team_code: team_code.to_string(),
+            rank: 0,
+            gp: 0,
+            points: 0,
+            diff: 0,
+            league: league.clone(),
+            w: 0,
+            l: 0,
+            reg_w: 0,
+            ot_w: 0,
+            so_w: 0,
+            reg_l: 0,
+            ot_l: 0,
+            so_l: 0,
+            gf: 0,
+            ga: 0,
+            home: StandingSplit::default(),
+            away: StandingSplit::default(),
+            last_five: vec![],
+            clinched: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LiveStanding {
+    #[serde(flatten)]
+    pub standing: Standing,
+    pub rank_change: i16, // positions gained compared to the table without the ongoing games
+    pub live: bool, // playing right now
+}
+
+/**
+ * The table as if every ongoing game ended with the current score,
+ * serialized as { "SHL": [...], "HA": [...] }
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct LiveStandings(pub BTreeMap<League, Vec<LiveStanding>>);
+
+/**
+ * The table of a league right after a finished game
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StandingsSnapshot {
+    pub game_uuid: String,
+    pub date: DateTime<Utc>,
+    pub league: League,
+    pub round: u16, // highest number of games played by any team
+    pub table: Vec<Standing>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RankPoint {
+    pub date: DateTime<Utc>,
+    pub gp: u16,
+    pub rank: u8,
+    pub points: u16,
 }
+
+/**
+ * Serialized as { "SHL": { "LHF": [...], ... }, ... }
+ */
+pub type StandingsTrajectory = BTreeMap<League, BTreeMap<TeamCode, Vec<RankPoint>>>;
@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use super::{game::ApiGame, event::ApiGameEvent, report::ApiGameReport};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiSync {
+    pub cursor: String, // pass back on the next sync
+    pub full_sync: bool, // the cursor is unknown or too old, fetch every season again
+    pub games: Vec<ApiGame>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub events: HashMap<String, Vec<ApiGameEvent>>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub reports: HashMap<String, ApiGameReport>,
+}
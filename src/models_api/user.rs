@@ -7,4 +7,10 @@ pub struct AddUser {
     pub apn_token: Option<String>,
     pub ios_version: Option<String>,
     pub app_version: Option<String>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub vote_results: Option<bool>, // push the outcome of the user's votes, on unless false
+}
+#[derive(Serialize, Deserialize)]
+pub struct UserCalendar {
+    pub path: String, // relative to the server, e.g. /v2/calendar/user/{user_id}/{token}.ics
+}
@@ -14,7 +14,19 @@ pub struct VotePerGame {
 }
 
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VoteAnomalies {
+    pub unknown_users: Vec<String>,
+    pub burst_users: Vec<String>,
+    pub nr_votes: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PurgeVotes {
+    pub user_ids: Option<Vec<String>>, // all users in the anomaly report if None
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Copy, PartialEq)]
 pub struct ApiVotePerGame {
     pub home_perc: u16,
     pub away_perc: u16,
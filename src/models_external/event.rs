@@ -213,7 +213,6 @@ pub struct EventTeam {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag="type", rename_all="lowercase")]
-
 pub enum EventType {
     Shot(ShotType),
     Period(PeriodType),
@@ -238,17 +237,13 @@ pub struct ShotType {
 
 impl LiveEventTeam {
     pub fn get_team_id(&self) -> String {
-        match self.teamId.as_str() {
-            _ => self.teamId.clone(),
-        }
+        self.teamId.clone()
     }
 }
 
 impl EventTeam {
     pub fn get_team_id(&self) -> String {
-        match self.teamId.as_str() {
-            _ => self.teamId.clone(),
-        }
+        self.teamId.clone()
     }
 }
 
@@ -398,9 +393,7 @@ impl TeamStatistics {
             .and_then(|e| e.value.as_ref().map(|a| a.to_num()))
     }
     pub fn get_team_id(&self) -> String {
-        match self.teamId.as_str() {
-            _ => self.teamId.clone(),
-        }
+        self.teamId.clone()
     }
 }
 
@@ -662,7 +655,7 @@ mod tests {
     
         let event: Vec<LiveEvent> = serde_json::from_str(json).expect("should pass");
         assert_eq!(event.len(), 1);
-        assert!(matches!(event.get(0).as_ref().unwrap().eventType.as_ref().unwrap(), EventType::Unknown));
+        assert!(matches!(event.first().unwrap().eventType.as_ref().unwrap(), EventType::Unknown));
     }
 
     #[test]
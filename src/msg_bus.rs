@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
 use tokio::sync::broadcast::{Sender, Receiver, self};
-use crate::{models_api::{event::ApiGameEvent, report::{ApiGameReport, GameStatus}}, LogResult, models_external::event::{LiveEvent, EventType}};
+use crate::{models_api::{event::ApiGameEvent, report::{ApiGameReport, GameStatus}}, LogResult, models_external::event::{LiveEvent, EventType}, playoff_service::PlayoffSeriesEvent};
 
 #[derive(Clone, Default)]
 pub struct UpdateReport {
@@ -99,8 +99,10 @@ pub enum Msg {
     AddEvent { event: ApiGameEvent, game_uuid: String },
     UpdateReport { report: UpdateReport, game_uuid: String, forced: bool },
     SseClosed { game_uuid: String },
-    ReportUpdated { report: ApiGameReport, game_uuid: String },
-    EventUpdated { event: ApiGameEvent, game_uuid: String },
+    ReportUpdated { #[allow(dead_code)] report: ApiGameReport, game_uuid: String }, // payload kept for websocket subscribers
+    EventUpdated { #[allow(dead_code)] event: ApiGameEvent, game_uuid: String },
+    PlayoffSeriesUpdated { event: PlayoffSeriesEvent, game_uuid: String }, // the game that changed the series
+    GameEnded { game_uuid: String }, // sent after the final ReportUpdated
 }
 
 impl Msg {
@@ -111,6 +113,8 @@ impl Msg {
             Msg::UpdateReport { report:_, game_uuid, forced: _ } => game_uuid,
             Msg::ReportUpdated { report:_, game_uuid } => game_uuid,
             Msg::EventUpdated { event:_, game_uuid } => game_uuid,
+            Msg::PlayoffSeriesUpdated { event:_, game_uuid } => game_uuid,
+            Msg::GameEnded { game_uuid } => game_uuid,
          }
     }
 }
@@ -1,10 +1,11 @@
-use std::time::Instant;
+use std::{collections::HashMap, time::Instant};
 
 use chrono::{Utc, Duration};
 use futures::{future::join_all, FutureExt};
+use serde::Serialize;
 use tracing::log;
 
-use crate::{event_service::EventService, user_service::{UserService, User}, apn_client::{ApnClient, ApnPush, ApnAlert, ApnBody, ApnHeader, ApnAps, LiveActivityContentState, ApnPushType, ApnError, LiveActivityReport, LiveActivityEvent}, CONFIG, api_teams_service::TeamsMap, models_api::{event::{ApiGameEvent, ApiEventType, ApiEventTypeLevel}, report::GameStatus, game::ApiGame}};
+use crate::{event_service::EventService, user_service::{UserService, User}, apn_client::{ApnClient, ApnPush, ApnAlert, ApnBody, ApnHeader, ApnAps, LiveActivityContentState, ApnPushType, ApnError, LiveActivityReport, LiveActivityEvent}, CONFIG, api_teams_service::TeamsMap, models_api::{event::{ApiGameEvent, ApiEventType, ApiEventTypeLevel}, report::GameStatus, game::ApiGame, standings::{Standings, Clinch}}, db::Db, models::{Season, PlayoffRoundName}, playoff_service::PlayoffSeriesEvent, vote_service::{Vote, VoteService}, pickem_service::PickemService};
 
 impl ApiGameEvent {
     fn get_time_info(&self) -> String {
@@ -35,7 +36,7 @@ impl ApiGameEvent {
     }
 }
 impl ApnAlert {
-    fn from(game: &ApiGame, event: &ApiGameEvent, teams: &TeamsMap, user_teams: &[String]) -> ApnAlert {
+    pub fn from(game: &ApiGame, event: &ApiGameEvent, teams: &TeamsMap, user_teams: &[String]) -> ApnAlert {
         match &event.info {
 
             ApiEventType::GameStart => {
@@ -84,6 +85,121 @@ impl ApnAlert {
     }
 }
 
+impl ApnAlert {
+    fn from_clinch(team_code: &str, clinch: &Clinch, teams: &TeamsMap) -> ApnAlert {
+        let name = teams.get_shortname(team_code);
+        let (title, body) = match clinch {
+            Clinch::FirstPlace => (format!("{name} vinner serien! 🏆"), format!("{name} är klara seriesegrare")),
+            Clinch::DirectPlayoff => (format!("{name} klara för slutspel! 🎉"), "Direktplatsen till slutspelet är säkrad".to_string()),
+            Clinch::Playoff => (format!("{name} klara för slutspel! 🎉"), "En plats i slutspelet är säkrad".to_string()),
+            Clinch::Demotion => (format!("{name} till kval"), "Det går inte längre att undvika kvalserien".to_string()),
+            Clinch::Eliminated => (format!("{name} missar slutspelet"), "Det går inte längre att nå en slutspelsplats".to_string()),
+        };
+        ApnAlert { title, body, subtitle: None }
+    }
+}
+
+impl ApnAlert {
+    fn from_playoff_series(event: &PlayoffSeriesEvent, teams: &TeamsMap) -> ApnAlert {
+        let entry = &event.entry;
+        let round_name = match event.round {
+            Some(PlayoffRoundName::Eight) => "Åttondelsfinal",
+            Some(PlayoffRoundName::Quarter) => "Kvartsfinal",
+            Some(PlayoffRoundName::Semi) => "Semifinal",
+            Some(PlayoffRoundName::Final) => "Final",
+            None => "Kvalserie",
+        };
+        let name1 = teams.get_shortname(&entry.team1);
+        let name2 = teams.get_shortname(&entry.team2);
+        let game_nr = entry.score1 + entry.score2 + 1;
+        let next_game = event.next_game.map(|e| match e.date_naive() == Utc::now().date_naive() {
+            true => "ikväll".to_string(),
+            false => e.format("%-d/%-m").to_string(),
+        });
+
+        if let Some(winner) = entry.get_winner() {
+            let (winner, loser, wins, losses) = match winner == entry.team1 {
+                true => (name1, name2, entry.score1, entry.score2),
+                false => (name2, name1, entry.score2, entry.score1),
+            };
+            let title = match event.round {
+                Some(PlayoffRoundName::Final) => format!("{winner} vinner finalen! 🏆"),
+                _ => format!("{winner} vinner serien"),
+            };
+            let body = format!("{loser} utslagna i {} • {wins}–{losses}", round_name.to_lowercase());
+            return ApnAlert { title, body, subtitle: None }
+        }
+        let title = match entry.score1.cmp(&entry.score2) {
+            std::cmp::Ordering::Greater => format!("{name1} leder {}–{}", entry.score1, entry.score2),
+            std::cmp::Ordering::Less => format!("{name2} leder {}–{}", entry.score2, entry.score1),
+            std::cmp::Ordering::Equal => format!("Lika i serien {}–{}", entry.score1, entry.score2),
+        };
+        let decisive = entry.score1 == entry.score2 && entry.score1 + 1 == entry.nr_games / 2 + 1;
+        let body = match (next_game, decisive) {
+            (Some(when), true) => format!("{round_name} {name1} - {name2} • Avgörande match {game_nr} {when}"),
+            (Some(when), false) => format!("{round_name} {name1} - {name2} • Match {game_nr} {when}"),
+            (None, _) => format!("{round_name} {name1} - {name2}"),
+        };
+        ApnAlert { title, body, subtitle: None }
+    }
+}
+
+impl ApnAlert {
+    fn from_vote_result(game: &ApiGame, team_code: &str, streak: u16, teams: &TeamsMap) -> ApnAlert {
+        let home_code = teams.get_display_code(&game.home_team_code);
+        let away_code = teams.get_display_code(&game.away_team_code);
+        let score_board = format!("{} {} - {} {}", home_code, game.home_team_result, game.away_team_result, away_code);
+        if game.did_team_win(team_code) {
+            let title = match streak {
+                0 | 1 => "Du tippade rätt! 🎯".to_string(),
+                streak => format!("Du tippade rätt! {streak} rätt i rad 🔥"),
+            };
+            ApnAlert { title, body: score_board, subtitle: None }
+        } else {
+            let winner = match game.home_team_code == team_code {
+                true => &game.away_team_code,
+                false => &game.home_team_code,
+            };
+            let title = "Du tippade fel".to_string();
+            let body = format!("{} vann • {score_board}", teams.get_shortname(winner));
+            ApnAlert { title, body, subtitle: None }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApnVoteResultData {
+    game_uuid: String,
+    team_code: String,
+    correct: bool,
+    streak: u16,
+}
+
+#[derive(Serialize)]
+struct ApnPlayoffSeriesData {
+    team1: String,
+    team2: String,
+    score1: u8,
+    score2: u8,
+}
+
+struct FollowerPush<D: Serialize> {
+    alert: ApnAlert,
+    data: D,
+    local_attachements: Vec<String>,
+    collapse_id: String,
+}
+
+fn get_clinch_key(team_code: &str, clinch: &Clinch) -> String {
+    format!("{team_code}/{clinch:?}")
+}
+
+#[derive(Serialize)]
+struct ApnClinchData {
+    team_code: String,
+    clinch: Clinch,
+}
+
 impl LiveActivityEvent {
     fn from(event: &ApiGameEvent, teams: &TeamsMap, user_teams: &[String]) -> LiveActivityEvent {
         match &event.info {
@@ -200,8 +316,7 @@ impl NotificationService {
     pub async fn process_live_activity(&mut self, game: &ApiGame) {
         let events = EventService::read(&game.game_uuid.clone());
         let event = events.iter()
-            .filter(|e| e.info.get_level() != ApiEventTypeLevel::Low)
-            .last();
+            .rfind(|e| e.info.get_level() != ApiEventTypeLevel::Low);
         let before = Instant::now();
         self.apn_client.update_token();
         let mut futures = vec!();
@@ -228,6 +343,156 @@ impl NotificationService {
         }
     }
 
+    /**
+     * Push every clinch not pushed before to the team's followers, only the strongest one per team.
+     * The first run of a season only records the current clinches
+     */
+    pub async fn process_clinches(&mut self, season: &Season, standings: &Standings) {
+        let new_clinches = NotificationService::record_clinches(season, standings);
+        if new_clinches.is_empty() {
+            return
+        }
+        let before = Instant::now();
+        let alerts: Vec<(&String, &Clinch, ApnAlert)> = new_clinches.iter()
+            .map(|(team_code, clinch)| (team_code, clinch, ApnAlert::from_clinch(team_code, clinch, &self.teams)))
+            .collect();
+        let size = self.push_to_followers(UserService::stream_all(), Duration::days(1), |user| alerts.iter()
+            .filter(|e| user.teams.contains(e.0))
+            .map(|(team_code, clinch, alert)| FollowerPush {
+                alert: alert.clone(),
+                data: ApnClinchData { team_code: team_code.to_string(), clinch: **clinch },
+                local_attachements: vec![team_code.to_string()],
+                collapse_id: get_clinch_key(team_code, clinch),
+            })
+            .collect()
+        ).await;
+        log::info!("[PUSH] Clinches {:?} to {} users in {:.0?}", new_clinches, size, before.elapsed());
+    }
+
+    /**
+     * Stores all current clinches of the season, returns the strongest not stored before per team
+     */
+    fn record_clinches(season: &Season, standings: &Standings) -> HashMap<String, Clinch> {
+        let db: Db<Season, Vec<String>> = Db::new("v2_clinch_pushed");
+        let current: Vec<(String, Clinch)> = standings.0.values()
+            .flatten()
+            .flat_map(|s| s.clinched.iter().map(|c| (s.team_code.clone(), *c)))
+            .collect();
+        let all_keys: Vec<String> = current.iter().map(|(t, c)| get_clinch_key(t, c)).collect();
+
+        let Some(mut pushed) = db.read(season) else {
+            _ = db.write(season, &all_keys);
+            return HashMap::new()
+        };
+        let mut new_clinches: HashMap<String, Clinch> = HashMap::new();
+        for (team_code, clinch) in current.iter().filter(|(t, c)| !pushed.contains(&get_clinch_key(t, c))) {
+            let entry = new_clinches.entry(team_code.clone()).or_insert(*clinch);
+            *entry = (*entry).min(*clinch);
+        }
+        pushed.extend(all_keys.into_iter().filter(|e| !pushed.contains(e)).collect::<Vec<String>>());
+        _ = db.write(season, &pushed);
+        new_clinches
+    }
+
+    /**
+     * Push a changed playoff series score to the followers of both teams
+     */
+    pub async fn process_playoff_series(&mut self, event: &PlayoffSeriesEvent) {
+        let before = Instant::now();
+        let entry = &event.entry;
+        let alert = ApnAlert::from_playoff_series(event, &self.teams);
+        let size = self.push_to_followers(UserService::stream_all(), Duration::hours(12), |user| {
+            if !user.teams.contains(&entry.team1) && !user.teams.contains(&entry.team2) {
+                return vec![]
+            }
+            vec![FollowerPush {
+                alert: alert.clone(),
+                data: ApnPlayoffSeriesData { team1: entry.team1.clone(), team2: entry.team2.clone(), score1: entry.score1, score2: entry.score2 },
+                local_attachements: vec![entry.team1.clone(), entry.team2.clone()],
+                collapse_id: format!("{}-{}", entry.team1, entry.team2),
+            }]
+        }).await;
+        log::info!("[PUSH] Playoff {event} to {} users in {:.0?}", size, before.elapsed());
+    }
+
+    /**
+     * Push the outcome to everyone who voted on the finished game, unless they turned it off or muted the game
+     */
+    pub async fn process_vote_results(&mut self, game: &ApiGame, games: &[ApiGame]) {
+        let votes = VoteService::read_votes();
+        let game_votes: Vec<&Vote> = votes.iter().filter(|e| e.game_uuid == game.game_uuid).collect();
+        if game_votes.is_empty() {
+            return
+        }
+        let stats = PickemService::get_vote_stats(games, &votes);
+        let before = Instant::now();
+        let alerts: Vec<(&Vote, u16, ApnAlert)> = game_votes.iter()
+            .map(|vote| {
+                let streak = stats.get(&vote.user_id).map(|e| e.current_streak).unwrap_or_default();
+                (*vote, streak, ApnAlert::from_vote_result(game, &vote.team_code, streak, &self.teams))
+            })
+            .collect();
+        let voters = game_votes.iter().filter_map(|e| UserService::read(&e.user_id));
+        let size = self.push_to_followers(voters, Duration::hours(12), |user| {
+            if user.vote_results == Some(false) || user.muted_games.contains(&game.game_uuid) {
+                return vec![]
+            }
+            alerts.iter()
+                .filter(|e| e.0.user_id == user.id)
+                .map(|(vote, streak, alert)| FollowerPush {
+                    alert: alert.clone(),
+                    data: ApnVoteResultData { game_uuid: game.game_uuid.clone(), team_code: vote.team_code.clone(), correct: game.did_team_win(&vote.team_code), streak: *streak },
+                    local_attachements: vec![vote.team_code.clone()],
+                    collapse_id: format!("{}-vote", game.game_uuid),
+                })
+                .collect()
+        }).await;
+        log::info!("[PUSH] Vote results {game} to {} users in {:.0?}", size, before.elapsed());
+    }
+
+    /**
+     * Sends the alerts get_pushes returns for each user with an apn token, returns the number of pushes
+     */
+    async fn push_to_followers<D: Serialize>(&mut self, users: impl Iterator<Item = User>, expires_in: Duration, get_pushes: impl Fn(&User) -> Vec<FollowerPush<D>>) -> usize {
+        self.apn_client.update_token();
+        let expiration = (Utc::now() + expires_in).timestamp();
+        let mut futures = vec!();
+        for user in users {
+            let Some(apn_token) = user.apn_token.clone() else {
+                continue
+            };
+            for push in get_pushes(&user) {
+                let body = ApnBody {
+                    aps: ApnAps {
+                        alert: Some(push.alert),
+                        sound: Some("ping.aiff".to_string()),
+                        content_state: None::<LiveActivityContentState>,
+                        ..Default::default()
+                    },
+                    data: push.data,
+                    local_attachements: push.local_attachements,
+                };
+                let header = ApnHeader {
+                    push_type: ApnPushType::Alert,
+                    priority: 100,
+                    topic: CONFIG.apn_topic.to_string(),
+                    collapse_id: Some(push.collapse_id),
+                    expiration: Some(expiration),
+                };
+                let user_id = user.id.clone();
+                let future = self.apn_client.push_notification(ApnPush { header, body }, apn_token.clone()).map(move |e| {
+                    if let Err(ApnError::BadDeviceToken) = e {
+                        UserService::remove_apn_token(&user_id);
+                    }
+                });
+                futures.push(future);
+            }
+        }
+        let size = futures.len();
+        join_all(futures).await;
+        size
+    }
+
     fn get_apn_push(&self, user: &User, game: &ApiGame, event: Option<&ApiGameEvent>, should_alert: bool) -> Option<(String, ApnPush<Option<LiveActivityContentState>, ApiGame>)> {
         let now = Utc::now().timestamp();
         let expiration = (Utc::now() + Duration::hours(1)).timestamp();
@@ -290,4 +555,100 @@ impl NotificationService {
             None
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use chrono::{Utc, TimeZone};
+    use tempdir::TempDir;
+
+    use crate::{api_teams_service::TeamsMap, apn_client::ApnAlert, models::{League, Season, GameType, PlayoffRoundName}, models_api::{game::ApiGame, report::GameStatus, standings::{Standings, Standing, Clinch}}, playoff_service::{PlayoffSeriesEvent, PlayoffEntry}};
+
+    use super::NotificationService;
+
+    fn before() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+    }
+
+    fn get_game() -> ApiGame {
+        ApiGame {
+            game_uuid: "notification_game1".to_string(),
+            home_team_code: "LHF".to_string(),
+            away_team_code: "FHC".to_string(),
+            home_team_result: 2,
+            away_team_result: 1,
+            start_date_time: Utc.with_ymd_and_hms(2016, 9, 16, 17, 0, 0).unwrap(),
+            status: GameStatus::Finished,
+            shootout: false,
+            overtime: false,
+            played: true,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2016),
+            gametime: None,
+            votes: None,
+        }
+    }
+
+    fn get_series(round: Option<PlayoffRoundName>, score1: u8, score2: u8) -> PlayoffSeriesEvent {
+        PlayoffSeriesEvent {
+            league: League::SHL,
+            round,
+            entry: PlayoffEntry { team1: "LHF".to_string(), team2: "FHC".to_string(), score1, score2, eliminated: None, nr_games: 7 },
+            next_game: None,
+        }
+    }
+
+    fn get_standings(clinched: Vec<Clinch>) -> Standings {
+        let standing = Standing { clinched, ..Standing::new("MODO", &League::SHL) };
+        Standings([(League::SHL, vec![standing])].into_iter().collect())
+    }
+
+    fn get_texts(alert: ApnAlert) -> (String, String) {
+        (alert.title, alert.body)
+    }
+
+    #[test]
+    fn clinch_alert() {
+        before();
+        let teams = TeamsMap::new();
+        assert_eq!(get_texts(ApnAlert::from_clinch("LHF", &Clinch::FirstPlace, &teams)), ("LHF vinner serien! 🏆".to_string(), "LHF är klara seriesegrare".to_string()));
+        assert_eq!(get_texts(ApnAlert::from_clinch("MODO", &Clinch::Demotion, &teams)), ("MODO till kval".to_string(), "Det går inte längre att undvika kvalserien".to_string()));
+    }
+
+    #[test]
+    fn playoff_series_alert() {
+        before();
+        let teams = TeamsMap::new();
+        let leading = ApnAlert::from_playoff_series(&get_series(Some(PlayoffRoundName::Quarter), 1, 2), &teams);
+        assert_eq!(get_texts(leading), ("FHC leder 2–1".to_string(), "Kvartsfinal LHF - FHC".to_string()));
+
+        let final_won = ApnAlert::from_playoff_series(&get_series(Some(PlayoffRoundName::Final), 4, 2), &teams);
+        assert_eq!(get_texts(final_won), ("LHF vinner finalen! 🏆".to_string(), "FHC utslagna i final • 4–2".to_string()));
+
+        let demotion = ApnAlert::from_playoff_series(&get_series(None, 3, 3), &teams);
+        assert_eq!(demotion.title, "Lika i serien 3–3");
+    }
+
+    #[test]
+    fn vote_result_alert() {
+        before();
+        let teams = TeamsMap::new();
+        assert_eq!(get_texts(ApnAlert::from_vote_result(&get_game(), "LHF", 1, &teams)), ("Du tippade rätt! 🎯".to_string(), "LHF 2 - 1 FHC".to_string()));
+        assert_eq!(ApnAlert::from_vote_result(&get_game(), "LHF", 3, &teams).title, "Du tippade rätt! 3 rätt i rad 🔥");
+        assert_eq!(get_texts(ApnAlert::from_vote_result(&get_game(), "FHC", 0, &teams)), ("Du tippade fel".to_string(), "LHF vann • LHF 2 - 1 FHC".to_string()));
+    }
+
+    #[test]
+    fn record_clinches() {
+        before();
+        let season = Season(1990);
+        // the first run only records
+        assert!(NotificationService::record_clinches(&season, &get_standings(vec![])).is_empty());
+
+        let new_clinches = NotificationService::record_clinches(&season, &get_standings(vec![Clinch::Demotion, Clinch::Eliminated]));
+        assert_eq!(new_clinches.get("MODO"), Some(&Clinch::Demotion));
+        assert_eq!(new_clinches.len(), 1);
+
+        assert!(NotificationService::record_clinches(&season, &get_standings(vec![Clinch::Demotion, Clinch::Eliminated])).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use std::{collections::{HashMap, hash_map::DefaultHasher}, hash::{Hash, Hasher}, sync::Mutex, time::Instant};
+
+use lazy_static::lazy_static;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use tracing::log;
+
+use crate::{db::Db, models::{GameType, League, Season, PlayoffFormat, PlayoffFormatRegistry}, models_api::{game::ApiGame, odds::{Odds, TeamOdds}, standings::{Standing, Standings}}, standing_service::StandingService, LogResult};
+
+lazy_static! {
+    // standings and remaining schedule the odds were last simulated from
+    static ref SIMULATED: Mutex<HashMap<Season, u64>> = Mutex::new(HashMap::new());
+}
+
+const NR_SIMULATIONS: usize = 10_000;
+const HOME_ADVANTAGE: f64 = 0.04;
+const OVERTIME_PROBABILITY: f64 = 0.22;
+
+#[derive(Clone, Copy)]
+struct SimulatedStanding {
+    points: u16,
+    diff: i16,
+    gf: u16,
+    tiebreak: u32, // stands in for head-to-head, which is not simulated
+}
+
+pub struct OddsService;
+impl OddsService {
+
+    /**
+     * Plays out the remaining schedule NR_SIMULATIONS times from the current standings
+     */
+    pub async fn update(season: &Season, games: &[ApiGame]) {
+        let season = season.clone();
+        let games = games.to_vec();
+        tokio::task::spawn_blocking(move || OddsService::update_blocking(&season, &games)).await
+            .ok_log("[ODDS] Simulation failed");
+    }
+
+    /**
+     * Only re-simulates when the standings or the remaining games changed since last time
+     */
+    fn update_blocking(season: &Season, games: &[ApiGame]) {
+        let before = Instant::now();
+        let Some(standings) = StandingService::read(season.clone()) else {
+            return
+        };
+        let fingerprint = OddsService::get_fingerprint(&standings, games);
+        if SIMULATED.lock().unwrap().get(season) == Some(&fingerprint) {
+            log::debug!("[ODDS] {season} unchanged");
+            return
+        }
+        let mut rng = StdRng::from_entropy();
+        let mut odds = Odds::default();
+        for league in League::get_all() {
+            let remaining: Vec<&ApiGame> = games.iter()
+                .filter(|e| e.game_type == GameType::Season)
+                .filter(|e| e.league == league)
+                .filter(|e| !e.played)
+                .collect();
+            let format = PlayoffFormatRegistry::get(&league, season);
+            let league_odds = OddsService::simulate(standings.get(&league), &remaining, format.as_ref(), NR_SIMULATIONS, &mut rng);
+            odds.0.insert(league, league_odds);
+        }
+        _ = OddsService::get_db().write(season, &odds);
+        SIMULATED.lock().unwrap().insert(season.clone(), fingerprint);
+        log::info!("[ODDS] Updated in {:.0?}", before.elapsed());
+    }
+
+    fn get_fingerprint(standings: &Standings, games: &[ApiGame]) -> u64 {
+        let mut remaining: Vec<&str> = games.iter()
+            .filter(|e| e.game_type == GameType::Season)
+            .filter(|e| !e.played)
+            .map(|e| e.game_uuid.as_str())
+            .collect();
+        remaining.sort();
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(standings).unwrap_or_default().hash(&mut hasher);
+        remaining.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn simulate<R: Rng>(table: &[Standing], remaining: &[&ApiGame], format: Option<&PlayoffFormat>, nr_simulations: usize, rng: &mut R) -> Vec<TeamOdds> {
+        let nr_teams = table.len();
+        let start: Vec<SimulatedStanding> = table.iter()
+            .map(|e| SimulatedStanding { points: e.points, diff: e.diff, gf: e.gf, tiebreak: 0 })
+            .collect();
+        let points_per_game: Vec<f64> = table.iter()
+            .map(|e| match e.gp {
+                0 => 1.5,
+                gp => f64::from(e.points) / f64::from(gp),
+            })
+            .collect();
+        let index_of = |team_code: &str| table.iter().position(|e| e.team_code == team_code);
+        let schedule: Vec<(usize, usize)> = remaining.iter()
+            .filter_map(|e| Some((index_of(&e.home_team_code)?, index_of(&e.away_team_code)?)))
+            .collect();
+
+        let mut rank_counts = vec![vec![0_u32; nr_teams]; nr_teams];
+        for _ in 0..nr_simulations {
+            let mut sim = start.clone();
+            for (home, away) in &schedule {
+                let p_home = (0.5 + HOME_ADVANTAGE + (points_per_game[*home] - points_per_game[*away]) / 6.0).clamp(0.15, 0.85);
+                let home_won = rng.gen_bool(p_home);
+                let overtime = rng.gen_bool(OVERTIME_PROBABILITY);
+                let (winner, loser) = match home_won {
+                    true => (*home, *away),
+                    false => (*away, *home),
+                };
+                let loser_goals: u16 = rng.gen_range(0..=3);
+                let margin: u16 = match overtime {
+                    true => 1,
+                    false => rng.gen_range(1..=3),
+                };
+                sim[winner].points += if overtime { 2 } else { 3 };
+                sim[loser].points += if overtime { 1 } else { 0 };
+                sim[winner].diff += margin as i16;
+                sim[loser].diff -= margin as i16;
+                sim[winner].gf += loser_goals + margin;
+                sim[loser].gf += loser_goals;
+            }
+            for e in sim.iter_mut() {
+                e.tiebreak = rng.gen();
+            }
+            let mut order: Vec<usize> = (0..nr_teams).collect();
+            order.sort_by_key(|i| std::cmp::Reverse((sim[*i].points, sim[*i].diff, sim[*i].gf, sim[*i].tiebreak)));
+            for (rank, team) in order.into_iter().enumerate() {
+                rank_counts[team][rank] += 1;
+            }
+        }
+
+        let direct = format.map(|e| e.get_direct_seeds()).unwrap_or_default();
+        let play_in = format.map(|e| e.get_play_in_seeds()).unwrap_or_default();
+        let demotion = format.map(|e| e.get_demotion_ranks()).unwrap_or_default();
+        let sum_of = |counts: &[u32], ranks: &[u8]| -> f32 {
+            let hits: u32 = ranks.iter()
+                .filter_map(|rank| counts.get(usize::from(*rank).wrapping_sub(1)))
+                .sum();
+            hits as f32 / nr_simulations.max(1) as f32
+        };
+        table.iter().zip(rank_counts.iter()).map(|(standing, counts)| {
+            let direct_playoff = sum_of(counts, &direct);
+            let play_in = sum_of(counts, &play_in);
+            TeamOdds {
+                team_code: standing.team_code.clone(),
+                ranks: counts.iter().map(|e| *e as f32 / nr_simulations.max(1) as f32).collect(),
+                playoff: direct_playoff + play_in,
+                direct_playoff,
+                play_in,
+                demotion: sum_of(counts, &demotion),
+            }
+        }).collect()
+    }
+
+    pub fn read_raw(season: &Season) -> String {
+        OddsService::get_db().read_raw(season)
+    }
+
+    fn get_db() -> Db<Season, Odds> {
+        Db::new("v2_odds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::{models::{League, PlayoffFormatRegistry, Season}, models_api::{game::ApiGame, report::GameStatus, standings::{Standing, Standings}}};
+
+    use super::OddsService;
+
+    #[test]
+    fn simulate() {
+        let table: Vec<Standing> = [("LHF", 30), ("FHC", 28), ("TIK", 10), ("MODO", 0)].iter().enumerate()
+            .map(|(i, (team, points))| Standing { rank: i as u8 + 1, gp: 10, points: *points, ..Standing::new(team, &League::SHL) })
+            .collect();
+        let games = [get_coming_game("LHF", "FHC"), get_coming_game("FHC", "LHF"), get_coming_game("TIK", "MODO")];
+        let remaining: Vec<&ApiGame> = games.iter().collect();
+        let format = PlayoffFormatRegistry::get(&League::SHL, &Season(2023));
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let odds = OddsService::simulate(&table, &remaining, format.as_ref(), 2000, &mut rng);
+
+        let lhf = &odds[0];
+        assert!(lhf.ranks[0] > 0.5 && lhf.ranks[0] < 1.0);
+        assert_eq!(lhf.ranks[2], 0.0);
+        assert_eq!(lhf.direct_playoff, 1.0);
+        assert_eq!(lhf.play_in, 0.0);
+
+        // TIK can not catch FHC with one game left, MODO can not catch TIK
+        let fhc = &odds[1];
+        assert!((fhc.ranks[0] + fhc.ranks[1] - 1.0).abs() < 0.001);
+        assert_eq!(odds[2].ranks[2], 1.0);
+        assert_eq!(odds[3].ranks[3], 1.0);
+
+        for team in &odds {
+            assert!((team.ranks.iter().sum::<f32>() - 1.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn fingerprint() {
+        let standings = Standings::default();
+        let games = vec![get_coming_game("LHF", "FHC"), get_coming_game("TIK", "MODO")];
+        let reversed: Vec<ApiGame> = games.iter().rev().cloned().collect();
+        let played = vec![ApiGame { played: true, ..get_coming_game("LHF", "FHC") }, get_coming_game("TIK", "MODO")];
+
+        let fingerprint = OddsService::get_fingerprint(&standings, &games);
+        assert_eq!(fingerprint, OddsService::get_fingerprint(&standings, &reversed));
+        assert_ne!(fingerprint, OddsService::get_fingerprint(&standings, &played));
+    }
+
+    fn get_coming_game(team1: &str, team2: &str) -> ApiGame {
+        ApiGame {
+            game_uuid: format!("{team1}-{team2}"),
+            home_team_code: team1.to_string(),
+            away_team_code: team2.to_string(),
+            home_team_result: 0,
+            away_team_result: 0,
+            start_date_time: Utc::now(),
+            status: GameStatus::Coming,
+            shootout: false,
+            overtime: false,
+            played: false,
+            game_type: crate::models::GameType::Season,
+            league: League::SHL,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use rand::{Rng, thread_rng};
+use tokio::sync::RwLock;
+use tracing::log;
+
+use crate::{db::Db, models_api::pickem::PickemGroup};
+
+const INVITE_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LEN: usize = 6;
+
+/**
+ * Writes go through the lock, a group is read, changed and written back
+ */
+pub struct PickemGroupService {
+    db: Db<String, PickemGroup>,
+}
+pub type SafePickemGroupService = Arc<RwLock<PickemGroupService>>;
+impl PickemGroupService {
+    pub fn new() -> SafePickemGroupService {
+        Arc::new(RwLock::new(PickemGroupService { db: PickemGroupService::get_db() }))
+    }
+
+    pub fn create(&mut self, user_id: &str, name: &str) -> PickemGroup {
+        let invite_code = loop {
+            let code = PickemGroupService::generate_invite_code();
+            if self.db.read(&code).is_none() {
+                break code;
+            }
+        };
+        let group = PickemGroup {
+            invite_code,
+            name: name.trim().to_string(),
+            owner_id: user_id.to_string(),
+            members: vec![user_id.to_string()],
+            created: Utc::now(),
+        };
+        _ = self.db.write(&group.invite_code, &group);
+        log::info!("[PICKEM] Created group {} {}", group.invite_code, group.name);
+        group
+    }
+
+    pub fn join(&mut self, invite_code: &str, user_id: &str) -> Option<PickemGroup> {
+        let invite_code = invite_code.trim().to_uppercase();
+        let mut group = self.db.read(&invite_code)?;
+        if !group.members.iter().any(|e| e == user_id) {
+            group.members.push(user_id.to_string());
+            _ = self.db.write(&invite_code, &group);
+        }
+        Some(group)
+    }
+
+    /**
+     * The oldest remaining member takes over when the owner leaves, the group is removed with its last member
+     */
+    pub fn leave(&mut self, invite_code: &str, user_id: &str) -> Option<PickemGroup> {
+        let invite_code = invite_code.trim().to_uppercase();
+        let mut group = self.db.read(&invite_code)?;
+        group.members.retain(|e| e != user_id);
+        if group.owner_id == user_id {
+            group.owner_id = group.members.first().cloned().unwrap_or_default();
+        }
+        if group.members.is_empty() {
+            _ = self.db.delete(&invite_code);
+            log::info!("[PICKEM] Removed empty group {} {}", group.invite_code, group.name);
+        } else {
+            _ = self.db.write(&invite_code, &group);
+        }
+        Some(group)
+    }
+
+    pub fn read(invite_code: &str) -> Option<PickemGroup> {
+        PickemGroupService::get_db().read(&invite_code.trim().to_uppercase())
+    }
+
+    pub fn read_for_user(user_id: &str) -> Vec<PickemGroup> {
+        PickemGroupService::get_db().stream_all()
+            .filter(|e| e.members.iter().any(|e| e == user_id))
+            .collect()
+    }
+
+    fn generate_invite_code() -> String {
+        let mut rng = thread_rng();
+        (0..INVITE_CODE_LEN)
+            .map(|_| char::from(INVITE_CODE_CHARS[rng.gen_range(0..INVITE_CODE_CHARS.len())]))
+            .collect()
+    }
+
+    fn get_db() -> Db<String, PickemGroup> {
+        Db::new("v2_pickem_groups")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::PickemGroupService;
+
+    #[test]
+    fn join_and_leave() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let service = PickemGroupService::new();
+        let mut service = service.try_write().unwrap();
+        let group = service.create("user1", " Kontoret ");
+        assert_eq!(group.invite_code.len(), 6);
+        assert_eq!(group.name, "Kontoret");
+
+        let joined = service.join(&group.invite_code.to_lowercase(), "user2").unwrap();
+        assert_eq!(joined.members, vec!["user1", "user2"]);
+        service.join(&group.invite_code, "user2");
+        assert_eq!(PickemGroupService::read(&group.invite_code).unwrap().members.len(), 2);
+        assert!(service.join("NOPE00", "user2").is_none());
+        assert!(PickemGroupService::read_for_user("user2").iter().any(|e| e.invite_code == group.invite_code));
+
+        let left = service.leave(&group.invite_code, "user1").unwrap();
+        assert_eq!(left.members, vec!["user2"]);
+        assert_eq!(left.owner_id, "user2");
+        assert!(PickemGroupService::read_for_user("user1").iter().all(|e| e.invite_code != group.invite_code));
+
+        let left = service.leave(&group.invite_code, "user2").unwrap();
+        assert!(left.members.is_empty());
+        assert!(PickemGroupService::read(&group.invite_code).is_none());
+    }
+}
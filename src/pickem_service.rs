@@ -0,0 +1,290 @@
+use std::{collections::{HashMap, BTreeMap}, time::Instant};
+
+use chrono::{DateTime, Datelike, Utc};
+use tracing::log;
+
+use crate::{db::Db, models::Season, models_api::{game::ApiGame, pickem::{PickemVote, PickemUserStats, PickemLeaderboards, PickemUserHistory, Prediction}, report::GameStatus}, vote_service::Vote};
+
+type DatedPrediction<'a> = (DateTime<Utc>, &'a Prediction); // start of the game
+
+pub struct PickemService;
+impl PickemService {
+
+    /**
+     * Score every vote and add the points of scored predictions on the season's games,
+     * then rank the users for the season and per week
+     */
+    pub fn update(season: &Season, games: &[ApiGame], votes: &[Vote], predictions: &[Prediction]) {
+        let before = Instant::now();
+        let votes_per_user = PickemService::get_votes_per_user(games, votes);
+        let predictions_per_user = PickemService::get_predictions_per_user(games, predictions);
+        let mut user_ids: Vec<&String> = votes_per_user.keys().chain(predictions_per_user.keys()).collect();
+        user_ids.sort();
+        user_ids.dedup();
+
+        let mut season_stats = vec![];
+        let mut weeks: BTreeMap<String, Vec<PickemUserStats>> = BTreeMap::new();
+        for user_id in user_ids {
+            let votes: Vec<&PickemVote> = votes_per_user.get(user_id).map(|e| e.iter().collect()).unwrap_or_default();
+            let predictions: Vec<&DatedPrediction> = predictions_per_user.get(user_id).map(|e| e.iter().collect()).unwrap_or_default();
+            season_stats.push(PickemService::get_stats(user_id, &votes, &predictions));
+
+            let mut per_week: BTreeMap<String, (Vec<&PickemVote>, Vec<&DatedPrediction>)> = BTreeMap::new();
+            for vote in votes {
+                per_week.entry(PickemService::get_week(&vote.start_date_time)).or_default().0.push(vote);
+            }
+            for prediction in predictions {
+                per_week.entry(PickemService::get_week(&prediction.0)).or_default().1.push(prediction);
+            }
+            for (week, (votes, predictions)) in per_week {
+                weeks.entry(week).or_default().push(PickemService::get_stats(user_id, &votes, &predictions));
+            }
+        }
+
+        let leaderboards = PickemLeaderboards {
+            season: PickemService::rank(season_stats),
+            weeks: weeks.into_iter().map(|(week, stats)| (week, PickemService::rank(stats))).collect(),
+        };
+        _ = PickemService::get_db().write(season, &leaderboards);
+        log::info!("[PICKEM] Updated {} users in {:.0?}", leaderboards.season.len(), before.elapsed());
+    }
+
+    /**
+     * Season stats per user from winner votes only
+     */
+    pub fn get_vote_stats(games: &[ApiGame], votes: &[Vote]) -> HashMap<String, PickemUserStats> {
+        PickemService::get_votes_per_user(games, votes).into_iter()
+            .map(|(user_id, votes)| {
+                let stats = PickemService::get_stats(&user_id, &votes.iter().collect::<Vec<&PickemVote>>(), &[]);
+                (user_id, stats)
+            })
+            .collect()
+    }
+
+    fn get_predictions_per_user<'a>(games: &[ApiGame], predictions: &'a [Prediction]) -> HashMap<String, Vec<DatedPrediction<'a>>> {
+        let games: HashMap<&str, &ApiGame> = games.iter().map(|e| (e.game_uuid.as_str(), e)).collect();
+        let mut result: HashMap<String, Vec<DatedPrediction>> = HashMap::new();
+        for prediction in predictions {
+            if let Some(game) = games.get(prediction.game_uuid.as_str()) {
+                result.entry(prediction.user_id.clone()).or_default().push((game.start_date_time, prediction));
+            }
+        }
+        result
+    }
+
+    fn get_votes_per_user(games: &[ApiGame], votes: &[Vote]) -> HashMap<String, Vec<PickemVote>> {
+        let games: HashMap<&str, &ApiGame> = games.iter().map(|e| (e.game_uuid.as_str(), e)).collect();
+        let mut result: HashMap<String, Vec<PickemVote>> = HashMap::new();
+        for vote in votes {
+            if let Some(game) = games.get(vote.game_uuid.as_str()) {
+                result.entry(vote.user_id.clone()).or_default().push(PickemService::score(game, vote));
+            }
+        }
+        for votes in result.values_mut() {
+            votes.sort_by_key(|e| e.start_date_time);
+        }
+        result
+    }
+
+    fn score(game: &ApiGame, vote: &Vote) -> PickemVote {
+        let correct = match game.played && game.status == GameStatus::Finished {
+            true => Some(game.did_team_win(&vote.team_code)),
+            false => None,
+        };
+        PickemVote {
+            game_uuid: game.game_uuid.clone(),
+            team_code: vote.team_code.clone(),
+            home_team_code: game.home_team_code.clone(),
+            away_team_code: game.away_team_code.clone(),
+            start_date_time: game.start_date_time,
+            league: game.league.clone(),
+            correct,
+        }
+    }
+
+    /**
+     * Votes ordered oldest first, one point per correct pick. Streaks only count winner votes
+     */
+    fn get_stats(user_id: &str, votes: &[&PickemVote], predictions: &[&DatedPrediction]) -> PickemUserStats {
+        let results: Vec<bool> = votes.iter().filter_map(|e| e.correct).collect();
+        let nr_correct = results.iter().filter(|e| **e).count() as u32;
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        for correct in &results {
+            streak = match correct {
+                true => streak + 1,
+                false => 0,
+            };
+            longest_streak = longest_streak.max(streak);
+        }
+        let scored: Vec<&Prediction> = predictions.iter().map(|e| e.1).filter(|e| e.correct.is_some()).collect();
+        let prediction_points: u32 = scored.iter().map(|e| e.points).sum();
+        PickemUserStats {
+            user_id: user_id.to_string(),
+            rank: 0,
+            points: nr_correct + prediction_points,
+            nr_votes: results.len() as u32,
+            nr_correct,
+            accuracy: match results.is_empty() {
+                true => 0.0,
+                false => nr_correct as f32 / results.len() as f32,
+            },
+            current_streak: streak,
+            longest_streak,
+            nr_predictions: scored.len() as u32,
+            prediction_points,
+        }
+    }
+
+    /**
+     * Ordered by points, then accuracy. Users on the same points share rank
+     */
+    fn rank(mut stats: Vec<PickemUserStats>) -> Vec<PickemUserStats> {
+        stats.retain(|e| e.nr_votes > 0 || e.nr_predictions > 0);
+        stats.sort_by(|a, b| b.points.cmp(&a.points)
+            .then_with(|| b.accuracy.total_cmp(&a.accuracy))
+            .then_with(|| a.user_id.cmp(&b.user_id)));
+        let mut rank = 0;
+        let mut last_points = None;
+        for (i, e) in stats.iter_mut().enumerate() {
+            if last_points != Some(e.points) {
+                rank = i as u32 + 1;
+                last_points = Some(e.points);
+            }
+            e.rank = rank;
+        }
+        stats
+    }
+
+    pub fn get_week(date: &DateTime<Utc>) -> String {
+        let week = date.iso_week();
+        format!("{}-W{:02}", week.year(), week.week())
+    }
+
+    /**
+     * The season leaderboard, or the one of the given ISO week
+     */
+    pub fn read_leaderboard(season: &Season, week: Option<&str>, limit: usize) -> Vec<PickemUserStats> {
+        let leaderboards = PickemService::get_db().read(season).unwrap_or_default();
+        let stats = match week {
+            Some(week) => leaderboards.weeks.get(week).cloned().unwrap_or_default(),
+            None => leaderboards.season,
+        };
+        stats.into_iter().take(limit).collect()
+    }
+
+    /**
+     * Same as read_leaderboard, ranked among the given users only
+     */
+    pub fn read_group_leaderboard(season: &Season, week: Option<&str>, user_ids: &[String]) -> Vec<PickemUserStats> {
+        let stats = PickemService::read_leaderboard(season, week, usize::MAX).into_iter()
+            .filter(|e| user_ids.contains(&e.user_id))
+            .collect();
+        PickemService::rank(stats)
+    }
+
+    pub fn read_user_history(season: &Season, user_id: &str, games: &[ApiGame], votes: &[Vote]) -> PickemUserHistory {
+        let user_votes: Vec<Vote> = votes.iter().filter(|e| e.user_id == user_id).cloned().collect();
+        let mut votes = PickemService::get_votes_per_user(games, &user_votes).remove(user_id).unwrap_or_default();
+        votes.reverse();
+        let season_stats = PickemService::get_db().read(season)
+            .and_then(|e| e.season.into_iter().find(|e| e.user_id == user_id));
+        PickemUserHistory {
+            user_id: user_id.to_string(),
+            season: season_stats,
+            votes,
+        }
+    }
+
+    fn get_db() -> Db<Season, PickemLeaderboards> {
+        Db::new("v2_pickem")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Utc, Duration, TimeZone};
+    use tempdir::TempDir;
+
+    use crate::{models::{League, Season, GameType}, models_api::{game::ApiGame, report::GameStatus, pickem::{Prediction, PredictionKind}}, vote_service::Vote};
+
+    use super::PickemService;
+
+    #[test]
+    fn leaderboard() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let monday = Utc.with_ymd_and_hms(2023, 10, 16, 18, 0, 0).unwrap();
+        let games = vec![
+            get_game("game1", "LHF", "FHC", Some(3), monday),
+            get_game("game2", "TIK", "MODO", Some(3), monday + Duration::days(1)),
+            get_game("game3", "LHF", "TIK", Some(4), monday + Duration::days(7)),
+            get_game("game4", "FHC", "MODO", None, monday + Duration::days(8)),
+        ];
+        let votes = vec![
+            vote("user1", "game1", "LHF"), vote("user1", "game2", "TIK"), vote("user1", "game3", "LHF"), vote("user1", "game4", "FHC"),
+            vote("user2", "game1", "LHF"), vote("user2", "game2", "MODO"), vote("user2", "game3", "LHF"),
+            vote("user3", "game1", "FHC"),
+            vote("user4", "game4", "FHC"),
+            vote("user5", "unknown_game", "FHC"),
+        ];
+        let season = Season(2023);
+        let predictions = vec![
+            prediction("user3", "game1", PredictionKind::ExactScore { home_team_result: 3, away_team_result: 2 }, Some(true), 5),
+            prediction("user3", "game3", PredictionKind::Overtime { overtime: true }, Some(false), 0),
+            prediction("user4", "game4", PredictionKind::Overtime { overtime: false }, None, 0),
+        ];
+        PickemService::update(&season, &games, &votes, &predictions);
+
+        let board = PickemService::read_leaderboard(&season, None, 10);
+        let ranks: Vec<(&str, u32, u32)> = board.iter().map(|e| (e.user_id.as_str(), e.rank, e.points)).collect();
+        assert_eq!(ranks, vec![("user3", 1, 5), ("user1", 2, 3), ("user2", 3, 2)]);
+        assert_eq!((board[0].nr_predictions, board[0].prediction_points), (2, 5));
+        assert_eq!((board[1].current_streak, board[1].longest_streak, board[1].nr_votes), (3, 3, 3));
+        assert_eq!((board[2].current_streak, board[2].longest_streak), (1, 1));
+
+        let week = PickemService::read_leaderboard(&season, Some("2023-W42"), 10);
+        let ranks: Vec<(&str, u32, u32)> = week.iter().map(|e| (e.user_id.as_str(), e.rank, e.points)).collect();
+        assert_eq!(ranks, vec![("user3", 1, 5), ("user1", 2, 2), ("user2", 3, 1)]);
+        let week = PickemService::read_leaderboard(&season, Some("2023-W43"), 1);
+        assert_eq!(week.len(), 1);
+        assert_eq!((week[0].rank, week[0].points), (1, 1));
+
+        let group = PickemService::read_group_leaderboard(&season, None, &["user2".to_string(), "user3".to_string()]);
+        let ranks: Vec<(&str, u32)> = group.iter().map(|e| (e.user_id.as_str(), e.rank)).collect();
+        assert_eq!(ranks, vec![("user3", 1), ("user2", 2)]);
+
+        let history = PickemService::read_user_history(&season, "user1", &games, &votes);
+        assert_eq!(history.season.map(|e| e.rank), Some(2));
+        assert_eq!(history.votes.len(), 4);
+        assert_eq!(history.votes[0].correct, None);
+        assert_eq!(history.votes[3].correct, Some(true));
+    }
+
+    fn prediction(user_id: &str, game_uuid: &str, kind: PredictionKind, correct: Option<bool>, points: u32) -> Prediction {
+        Prediction { user_id: user_id.to_string(), game_uuid: game_uuid.to_string(), kind, correct, points }
+    }
+
+    fn vote(user_id: &str, game_uuid: &str, team_code: &str) -> Vote {
+        Vote { user_id: user_id.to_string(), game_uuid: game_uuid.to_string(), team_code: team_code.to_string(), is_home_winner: false, created: None }
+    }
+
+    fn get_game(game_uuid: &str, home: &str, away: &str, home_result: Option<i16>, start_date_time: chrono::DateTime<Utc>) -> ApiGame {
+        ApiGame {
+            game_uuid: game_uuid.to_string(),
+            home_team_code: home.to_string(),
+            away_team_code: away.to_string(),
+            home_team_result: home_result.unwrap_or_default(),
+            away_team_result: 2,
+            start_date_time,
+            status: match home_result { Some(_) => GameStatus::Finished, None => GameStatus::Coming },
+            shootout: false,
+            overtime: false,
+            played: home_result.is_some(),
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2023),
+            gametime: None,
+            votes: None,
+        }
+    }
+}
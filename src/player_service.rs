@@ -23,7 +23,7 @@ impl ApiAthlete {
             first_name: name.firstName,
             family_name: name.lastName,
             jersey: gk.NR,
-            season: Season::Season2023,
+            season: Season::get_current(),
             league,
             team_code: gk.info.teamId,
             position: "GK".to_string(),
@@ -49,7 +49,7 @@ impl ApiAthlete {
             first_name: name.firstName,
             family_name: name.lastName,
             jersey: p.NR,
-            season: Season::Season2023,
+            season: Season::get_current(),
             league,
             team_code: p.info.teamId,
             position: p.POS.to_str(),
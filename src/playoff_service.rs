@@ -1,10 +1,12 @@
-use std::time::Instant;
+use std::{collections::BTreeMap, fmt::Display, sync::Arc, time::Instant};
+
+use chrono::{DateTime, Utc};
 
 use serde::{Serialize, Deserialize};
 
-use crate::{db::Db, models::{GameType, Season}, models_api::game::ApiGame, LogResult};
+use crate::{db::Db, response_cache::{ResponseCache, CachedResponse}, models::{GameType, League, Season, PlayoffFormat, PlayoffFormatRegistry, PlayoffRoundName, PlayoffSeed, DemotionFormat}, models_api::{game::ApiGame, standings::Standing}, standing_service::StandingService, LogResult};
 use tracing::log;
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PlayoffEntry {
     pub team1: String,
     pub team2: String,
@@ -20,19 +22,53 @@ fn get_nr_games() -> u8 {
 }
 
 impl PlayoffEntry {
-    fn clone_with(&self, score1: u8, score2: u8) -> PlayoffEntry {
+    fn new(team1: &str, team2: &str, nr_games: u8) -> PlayoffEntry {
         PlayoffEntry {
+            team1: team1.to_string(),
+            team2: team2.to_string(),
+            score1: 0,
+            score2: 0,
+            eliminated: None,
+            nr_games,
+        }
+    }
+
+    fn clone_with(&self, score1: u8, score2: u8) -> PlayoffEntry {
+        let mut entry = PlayoffEntry {
             team1: self.team1.clone(),
             team2: self.team2.clone(),
             score1,
             score2,
             eliminated: self.eliminated.clone(),
             nr_games: self.nr_games
+        };
+        if let Some(winner) = entry.get_winner() {
+            entry.eliminated = Some(match winner == entry.team1 {
+                true => entry.team2.clone(),
+                false => entry.team1.clone(),
+            });
+        }
+        entry
+    }
+
+    /**
+     * The team that has won more than half of the nr_games, if any
+     */
+    pub fn get_winner(&self) -> Option<String> {
+        let needed = self.nr_games / 2 + 1;
+        if self.team1 == TBD || self.team2 == TBD {
+            None
+        } else if self.score1 >= needed {
+            Some(self.team1.clone())
+        } else if self.score2 >= needed {
+            Some(self.team2.clone())
+        } else {
+            None
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct PlayoffSeries {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub eight: Vec<PlayoffEntry>,
@@ -50,36 +86,284 @@ pub struct PlayoffSeries {
     pub demotion: Option<PlayoffEntry>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Playoffs {
-    pub SHL: PlayoffSeries,
-    pub HA: PlayoffSeries,
+impl PlayoffSeries {
+    fn get_round(&self, name: PlayoffRoundName) -> Vec<PlayoffEntry> {
+        match name {
+            PlayoffRoundName::Eight => self.eight.clone(),
+            PlayoffRoundName::Quarter => self.quarter.clone(),
+            PlayoffRoundName::Semi => self.semi.clone(),
+            PlayoffRoundName::Final => self.final_.iter().cloned().collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self == &PlayoffSeries::default()
+    }
+
+    fn set_round(&mut self, name: PlayoffRoundName, entries: Vec<PlayoffEntry>) {
+        match name {
+            PlayoffRoundName::Eight => self.eight = entries,
+            PlayoffRoundName::Quarter => self.quarter = entries,
+            PlayoffRoundName::Semi => self.semi = entries,
+            PlayoffRoundName::Final => self.final_ = entries.into_iter().next(),
+        }
+    }
+}
+
+/**
+ * Serialized as { "SHL": {...}, "HA": {...} }, one entry per league, empty until seeded
+ */
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct Playoffs(pub BTreeMap<League, PlayoffSeries>);
+
+#[derive(Clone, Debug)]
+pub struct PlayoffSeriesEvent {
+    pub league: League,
+    pub round: Option<PlayoffRoundName>, // None for the demotion series
+    pub entry: PlayoffEntry,
+    pub next_game: Option<DateTime<Utc>>,
+}
+
+impl Display for PlayoffSeriesEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?} {} {} - {} {}", self.league, self.round, self.entry.team1, self.entry.score1, self.entry.score2, self.entry.team2)
+    }
 }
 
+const TBD: &str = "TBD";
+
 pub struct PlayoffService;
 impl PlayoffService {
-    pub fn update(season: &Season, games: &[ApiGame]) {
+    /**
+     * Returns an event for every series whose score or eliminated team changed. Only the current season is seeded from standings
+     */
+    pub fn update(season: &Season, games: &[ApiGame]) -> Vec<PlayoffSeriesEvent> {
+        PlayoffService::update_and_seed(season, games, season.is_current())
+    }
+
+    fn update_and_seed(season: &Season, games: &[ApiGame], auto_seed: bool) -> Vec<PlayoffSeriesEvent> {
         let before = Instant::now();
         let db = PlayoffService::get_db();
+        let standings = StandingService::read(season.clone()).unwrap_or_default();
+        let mut play_offs = db.read(season);
+        let previous = play_offs.clone().unwrap_or_default();
+        let mut seeded = false;
+        for league in League::get_all() {
+            let has_bracket = play_offs.as_ref().and_then(|e| e.0.get(&league)).map(|e| !e.is_empty()).unwrap_or(false);
+            if !auto_seed || has_bracket || !PlayoffService::is_season_finished(&league, games) {
+                continue;
+            }
+            let series = PlayoffFormatRegistry::get(&league, season)
+                .and_then(|format| PlayoffService::seed(&format, standings.get(&league)));
+            if let Some(series) = series {
+                log::info!("[PLAYOFF] Seeded {league} {season} from standings");
+                play_offs.get_or_insert_with(Playoffs::default).0.insert(league, series);
+                seeded = true;
+            }
+        }
         let applicable_games: Vec<ApiGame> = games.iter()
             .filter(|e| e.game_type != GameType::Season)
             .filter(|e| e.played)
             .cloned()
             .collect();
-        if applicable_games.is_empty() {
-            return
+        if applicable_games.is_empty() && !seeded {
+            return vec![]
+        }
+        let Some(play_offs) = play_offs else {
+            return vec![]
+        };
+        let mut updated = Playoffs(play_offs.0.into_iter().map(|(league, series)| {
+            let league_games: Vec<ApiGame> = applicable_games.iter()
+                .filter(|e| e.league == league)
+                .cloned()
+                .collect();
+            let mut series = PlayoffService::update_series(series, &league_games);
+            if let Some(format) = PlayoffFormatRegistry::get(&league, season) {
+                loop {
+                    let advanced = PlayoffService::advance(&format, series.clone(), standings.get(&league));
+                    let advanced = PlayoffService::update_series(advanced, &league_games);
+                    if advanced == series {
+                        break;
+                    }
+                    series = advanced;
+                }
+            }
+            (league, series)
+        }).collect());
+        // leagues without a bracket yet are still listed, as the SHL and HA fields used to be
+        for league in League::get_all() {
+            updated.0.entry(league).or_default();
+        }
+        PlayoffService::resolve_demotion(&mut updated, season);
+        // qualification games may be listed under either league
+        for series in updated.0.values_mut() {
+            let demotion = PlayoffSeries { demotion: series.demotion.take(), ..Default::default() };
+            series.demotion = PlayoffService::update_series(demotion, &applicable_games).demotion;
+        }
+        db.write_pretty(season, &updated, true)
+            .ok_log("[PLAYOFF] failed to write");
+        ResponseCache::invalidate(&format!("playoffs/{}", season));
+        log::info!("[PLAYOFF] Updated in {:.0?}", before.elapsed());
+        PlayoffService::get_events(&previous, &updated, games)
+    }
+
+    fn get_events(previous: &Playoffs, updated: &Playoffs, games: &[ApiGame]) -> Vec<PlayoffSeriesEvent> {
+        let rounds = [Some(PlayoffRoundName::Eight), Some(PlayoffRoundName::Quarter), Some(PlayoffRoundName::Semi), Some(PlayoffRoundName::Final), None];
+        let get_entries = |series: Option<&PlayoffSeries>, round: Option<PlayoffRoundName>| -> Vec<PlayoffEntry> {
+            match round {
+                Some(name) => series.map(|e| e.get_round(name)).unwrap_or_default(),
+                None => series.and_then(|e| e.demotion.clone()).into_iter().collect(),
+            }
+        };
+        let mut events = vec![];
+        for (league, series) in &updated.0 {
+            for round in rounds {
+                let old_entries = get_entries(previous.0.get(league), round);
+                for entry in get_entries(Some(series), round) {
+                    let old = old_entries.iter().find(|e| e.team1 == entry.team1 && e.team2 == entry.team2);
+                    let changed = match old {
+                        Some(old) => old.score1 != entry.score1 || old.score2 != entry.score2 || old.eliminated != entry.eliminated,
+                        None => entry.score1 + entry.score2 > 0,
+                    };
+                    if !changed {
+                        continue;
+                    }
+                    let next_game = games.iter()
+                        .filter(|e| !e.played && e.game_type != GameType::Season)
+                        .filter(|e| e.home_team_code == entry.team1 || e.home_team_code == entry.team2)
+                        .filter(|e| e.away_team_code == entry.team1 || e.away_team_code == entry.team2)
+                        .map(|e| e.start_date_time)
+                        .min();
+                    events.push(PlayoffSeriesEvent { league: league.clone(), round, entry, next_game });
+                }
+            }
+        }
+        events
+    }
+
+    fn is_season_finished(league: &League, games: &[ApiGame]) -> bool {
+        let mut season_games = games.iter()
+            .filter(|e| &e.league == league)
+            .filter(|e| e.game_type == GameType::Season)
+            .peekable();
+        season_games.peek().is_some() && season_games.all(|e| e.played)
+    }
+
+    /**
+     * Entries for every round that has teams seeded directly, with TBD for the
+     * teams still to come from the previous round
+     */
+    fn seed(format: &PlayoffFormat, table: &[Standing]) -> Option<PlayoffSeries> {
+        // ranks start at 1, a format with a rank outside the table is not seeded at all
+        let get_team = |rank: &u8| usize::from(*rank).checked_sub(1)
+            .and_then(|i| table.get(i))
+            .map(|e| e.team_code.clone());
+        let mut all_seeds = format.rounds.iter().flat_map(|e| e.seeds.iter()).peekable();
+        if all_seeds.peek().is_none() || !all_seeds.all(|e| get_team(e).is_some()) {
+            return None
+        }
+        let team = |rank: &u8| get_team(rank).unwrap_or_else(|| TBD.to_string());
+
+        let mut series = PlayoffSeries::default();
+        let mut nr_from_previous = 0;
+        for round in &format.rounds {
+            let mut seeds = round.seeds.clone();
+            seeds.sort();
+            let nr_tbd = nr_from_previous.min(seeds.len());
+            let mut entries: Vec<PlayoffEntry> = seeds[..nr_tbd].iter()
+                .map(|e| PlayoffEntry::new(&team(e), TBD, round.best_of))
+                .collect();
+            let rest = &seeds[nr_tbd..];
+            entries.extend((0..rest.len() / 2)
+                .map(|i| PlayoffEntry::new(&team(&rest[i]), &team(&rest[rest.len() - 1 - i]), round.best_of)));
+            // a round without seeds is created once the previous round is decided
+            if nr_tbd == nr_from_previous {
+                series.set_round(round.name, entries);
+            }
+            nr_from_previous = (nr_from_previous + seeds.len()) / 2;
+        }
+        if let Some(demotion) = &format.demotion {
+            let get_team = |seed: &PlayoffSeed| match seed {
+                PlayoffSeed::Rank(rank) => get_team(rank),
+                PlayoffSeed::FinalLoser(_) => Some(TBD.to_string()),
+            };
+            if let (Some(team1), Some(team2)) = (get_team(&demotion.team1), get_team(&demotion.team2)) {
+                series.demotion = Some(PlayoffEntry::new(&team1, &team2, demotion.best_of));
+            }
         }
-        if let Some(play_offs) = db.read(season) {
-            let updated = Playoffs {
-                HA: PlayoffService::update_series(play_offs.HA, &applicable_games),
-                SHL: PlayoffService::update_series(play_offs.SHL, &applicable_games),    
+        Some(series)
+    }
+
+    /**
+     * Fill in the next round once every series in the previous one is decided. With reseeding
+     * the best remaining seed meets the worst, otherwise the bracket order is kept
+     */
+    fn advance(format: &PlayoffFormat, mut series: PlayoffSeries, table: &[Standing]) -> PlayoffSeries {
+        let get_seed = |team: &String| table.iter().find(|e| &e.team_code == team).map(|e| e.rank).unwrap_or(u8::MAX);
+
+        for (previous, round) in format.rounds.iter().zip(format.rounds.iter().skip(1)) {
+            let Some(mut winners) = PlayoffService::get_winners(&series.get_round(previous.name)) else {
+                continue
             };
-            db.write_pretty(season, &updated, true)
-                .ok_log("[PLAYOFF] failed to write");
-            log::info!("[PLAYOFF] Updated in {:.0?}", before.elapsed());
+            let mut entries = series.get_round(round.name);
+            if entries.iter().any(|e| e.team2 == TBD) {
+                if round.reseed {
+                    winners.sort_by_key(|e| std::cmp::Reverse(get_seed(e)));
+                }
+                for entry in entries.iter_mut().filter(|e| e.team2 == TBD) {
+                    if winners.is_empty() {
+                        break;
+                    }
+                    entry.team2 = winners.remove(0);
+                }
+            } else if entries.is_empty() {
+                if round.reseed {
+                    winners.sort_by_key(get_seed);
+                    let n = winners.len();
+                    entries = (0..n / 2)
+                        .map(|i| PlayoffEntry::new(&winners[i], &winners[n - 1 - i], round.best_of))
+                        .collect();
+                } else {
+                    entries = winners.chunks(2)
+                        .filter(|e| e.len() == 2)
+                        .map(|e| PlayoffEntry::new(&e[0], &e[1], round.best_of))
+                        .collect();
+                }
+            }
+            series.set_round(round.name, entries);
+        }
+        series
+    }
+
+    /**
+     * Qualification series against another league's losing finalist
+     */
+    fn resolve_demotion(play_offs: &mut Playoffs, season: &Season) {
+        let leagues: Vec<League> = play_offs.0.keys().cloned().collect();
+        for league in leagues {
+            let Some(DemotionFormat { team2: PlayoffSeed::FinalLoser(other), .. }) = PlayoffFormatRegistry::get(&league, season).and_then(|e| e.demotion) else {
+                continue
+            };
+            let loser = play_offs.0.get(&other)
+                .and_then(|e| e.final_.as_ref())
+                .filter(|e| e.get_winner().is_some())
+                .and_then(|e| e.eliminated.clone());
+            if let (Some(loser), Some(demotion)) = (loser, play_offs.0.get_mut(&league).and_then(|e| e.demotion.as_mut())) {
+                if demotion.team2 == TBD {
+                    demotion.team2 = loser;
+                }
+            }
         }
     }
 
+    fn get_winners(round: &[PlayoffEntry]) -> Option<Vec<String>> {
+        if round.is_empty() {
+            return None
+        }
+        round.iter().map(|e| e.get_winner()).collect()
+    }
+
     fn update_series(series: PlayoffSeries, applicable_games: &[ApiGame]) -> PlayoffSeries {
         PlayoffSeries {
             final_: series.final_.map(|e| {
@@ -111,7 +395,7 @@ impl PlayoffService {
     }
 
     fn get_score(team: &str, opponent: &str, games: &[ApiGame]) -> u8 {
-        if team == "TBD" || opponent == "TBD" {
+        if team == TBD || opponent == TBD {
             return 0
         }
         games.iter()
@@ -125,6 +409,13 @@ impl PlayoffService {
             })
     }
     
+    pub fn read_cached(season: &Season) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("playoffs/{}", season), || {
+            let db = PlayoffService::get_db();
+            (db.read_raw(season), db.last_modified(season))
+        })
+    }
+
     pub fn get_db() -> Db<Season, Playoffs> {
         Db::new("v2_playoffs")
     }
@@ -132,10 +423,12 @@ impl PlayoffService {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use chrono::Utc;
     use tempdir::TempDir;
 
-    use crate::{models_api::{game::ApiGame, report::GameStatus}, playoff_service::{PlayoffEntry, PlayoffSeries, Playoffs}, LogResult};
+    use crate::{models::{League, GameType, PlayoffRoundName, PlayoffFormatRegistry, Season}, models_api::{game::ApiGame, report::GameStatus, standings::Standing}, playoff_service::{PlayoffEntry, PlayoffSeries, Playoffs}, standing_service::StandingService, LogResult};
 
     use super::PlayoffService;
 
@@ -150,8 +443,7 @@ mod tests {
             get_played_game("game_uuid", "team3", "team4"),
         ];
         let db = PlayoffService::get_db();
-        db.write(&crate::models::Season::Season2023, &Playoffs {
-            SHL: PlayoffSeries {
+        db.write(&crate::models::Season(2023), &Playoffs(BTreeMap::from([(League::SHL, PlayoffSeries {
                 quarter: vec![PlayoffEntry { 
                     team1: "team1".to_string(), 
                     team2: "team2".to_string(), 
@@ -169,29 +461,147 @@ mod tests {
                     nr_games: 7, 
                 }),
                 ..Default::default()
-            },
-            HA: PlayoffSeries {
+            }),
+            (League::HA, PlayoffSeries {
                 ..Default::default()
-            }
-        }).ok_log("msg");
+            })
+        ]))).ok_log("msg");
 
-        PlayoffService::update(&crate::models::Season::Season2023, &games);
+        PlayoffService::update(&crate::models::Season(2023), &games);
         
-        let result = db.read(&crate::models::Season::Season2023);
+        let result = db.read(&crate::models::Season(2023));
         assert!(result.is_some());
         let play_off = result.unwrap();
-        assert!(play_off.HA.final_.is_none());
-        assert_eq!(play_off.SHL.quarter.len(), 1);
-        let quarter = play_off.SHL.quarter.get(0).unwrap();
+        assert!(play_off.0[&League::HA].final_.is_none());
+        assert_eq!(play_off.0[&League::SHL].quarter.len(), 1);
+        let quarter = play_off.0[&League::SHL].quarter.first().unwrap();
         assert_eq!(quarter.score1, 2);
         assert_eq!(quarter.score2, 1);
         assert_eq!(quarter.eliminated.as_ref().unwrap(), "team1");
-        let demotion = play_off.SHL.demotion.unwrap();
+        let demotion = play_off.0[&League::SHL].demotion.clone().unwrap();
         assert_eq!(demotion.score1, 1);
         assert_eq!(demotion.score2, 0);
         assert!(demotion.eliminated.is_none());
     }
 
+    #[test]
+    fn seed_from_standings() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let season = crate::models::Season(2021);
+        let mut games = get_season_games("T", 14, League::SHL);
+        StandingService::update(&season, &games);
+        // past seasons are not seeded on a regular update
+        PlayoffService::update(&season, &games);
+        assert!(PlayoffService::get_db().read(&season).is_none());
+        PlayoffService::update_and_seed(&season, &games, true);
+
+        // HA has no games, it is still listed
+        assert!(StandingService::read(season.clone()).unwrap().0.get(&League::HA).is_some_and(|e| e.is_empty()));
+        assert!(PlayoffService::get_db().read(&season).unwrap().0.get(&League::HA).is_some_and(|e| e.is_empty()));
+
+        let series = PlayoffService::get_db().read(&season).unwrap().0.remove(&League::SHL).unwrap();
+        assert_eq!((series.eight[0].team1.as_str(), series.eight[0].team2.as_str()), ("T07", "T10"));
+        assert_eq!(series.eight[0].nr_games, 3);
+        assert_eq!((series.quarter[0].team1.as_str(), series.quarter[0].team2.as_str()), ("T01", "TBD"));
+        assert_eq!((series.quarter[3].team1.as_str(), series.quarter[3].team2.as_str()), ("T04", "T05"));
+        let demotion = series.demotion.unwrap();
+        assert_eq!((demotion.team1.as_str(), demotion.team2.as_str()), ("T13", "TBD"));
+        assert!(series.semi.is_empty());
+
+        // lowest seeds win the eighth finals, T05 upsets T04
+        let series_games = [("T10", "T07", 2), ("T08", "T09", 2), ("T01", "T10", 4), ("T02", "T08", 4), ("T03", "T06", 4), ("T05", "T04", 4)];
+        for (winner, loser, nr) in series_games {
+            for _ in 0..nr {
+                games.push(get_played_game("game_uuid", winner, loser));
+            }
+        }
+        let events = PlayoffService::update_and_seed(&season, &games, true);
+        assert_eq!(events.len(), 6);
+        assert!(events.iter().all(|e| e.entry.get_winner().is_some()));
+
+        let series = PlayoffService::get_db().read(&season).unwrap().0.remove(&League::SHL).unwrap();
+        assert_eq!(series.eight[0].eliminated.as_deref(), Some("T07"));
+        assert_eq!((series.quarter[0].team1.as_str(), series.quarter[0].team2.as_str()), ("T01", "T10"));
+        assert_eq!((series.quarter[1].team1.as_str(), series.quarter[1].team2.as_str()), ("T02", "T08"));
+        assert_eq!(series.quarter[3].eliminated.as_deref(), Some("T04"));
+        assert_eq!(series.semi.len(), 2);
+        assert_eq!((series.semi[0].team1.as_str(), series.semi[0].team2.as_str()), ("T01", "T05"));
+        assert_eq!((series.semi[1].team1.as_str(), series.semi[1].team2.as_str()), ("T02", "T03"));
+        assert!(series.final_.is_none());
+
+        games.push(get_played_game("game_uuid", "T05", "T01"));
+        games.push(ApiGame { played: false, status: GameStatus::Coming, ..get_played_game("game_uuid", "T01", "T05") });
+        let events = PlayoffService::update_and_seed(&season, &games, true);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].round, Some(PlayoffRoundName::Semi));
+        assert_eq!((events[0].entry.score1, events[0].entry.score2), (0, 1));
+        assert!(events[0].next_game.is_some());
+    }
+
+    #[test]
+    fn league_and_season_formats() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let mut games = get_season_games("S", 14, League::SHL);
+        games.extend(get_season_games("H", 10, League::HA));
+
+        // no playoffs in 2019
+        StandingService::update(&crate::models::Season(2019), &games);
+        PlayoffService::update_and_seed(&crate::models::Season(2019), &games, true);
+        assert!(PlayoffService::get_db().read(&crate::models::Season(2019)).is_none());
+
+        let season = crate::models::Season(2022);
+        StandingService::update(&season, &games);
+        PlayoffService::update_and_seed(&season, &games, true);
+        let series = PlayoffService::get_db().read(&season).unwrap().0.remove(&League::HA).unwrap();
+        assert_eq!((series.eight[0].team1.as_str(), series.eight[0].team2.as_str()), ("H04", "H07"));
+        assert_eq!((series.quarter[1].team1.as_str(), series.quarter[1].team2.as_str()), ("H03", "TBD"));
+        assert_eq!(series.quarter[1].nr_games, 5);
+        assert!(series.semi.is_empty());
+        assert_eq!(series.final_.as_ref().map(|e| e.team1.as_str()), Some("H01"));
+
+        let series_games = [("H04", "H07", 2), ("H05", "H06", 2), ("H02", "H05", 3), ("H04", "H03", 3), ("H04", "H02", 4), ("H01", "H04", 4)];
+        for (winner, loser, nr) in series_games {
+            for _ in 0..nr {
+                games.push(ApiGame { league: League::HA, ..get_played_game("game_uuid", winner, loser) });
+            }
+        }
+        PlayoffService::update_and_seed(&season, &games, true);
+        let mut play_offs = PlayoffService::get_db().read(&season).unwrap();
+        let series = play_offs.0.remove(&League::HA).unwrap();
+        assert_eq!((series.semi[0].team1.as_str(), series.semi[0].team2.as_str()), ("H02", "H04"));
+        let final_ = series.final_.unwrap();
+        assert_eq!((final_.team1.as_str(), final_.team2.as_str(), final_.score1), ("H01", "H04", 4));
+        assert_eq!(final_.eliminated.as_deref(), Some("H04"));
+
+        // the losing HA finalist meets SHL's 13th
+        let demotion = play_offs.0.remove(&League::SHL).unwrap().demotion.unwrap();
+        assert_eq!((demotion.team1.as_str(), demotion.team2.as_str()), ("S13", "H04"));
+    }
+
+    #[test]
+    fn seed_invalid_ranks() {
+        let table: Vec<Standing> = (1..=14).map(|i| Standing { rank: i, ..Standing::new(&format!("T{i:02}"), &League::SHL) }).collect();
+        let format = PlayoffFormatRegistry::get(&League::SHL, &Season(2023)).unwrap();
+        assert!(PlayoffService::seed(&format, &table).is_some());
+
+        let mut format = format;
+        format.rounds[0].seeds.push(0);
+        assert!(PlayoffService::seed(&format, &table).is_none());
+        format.rounds[0].seeds = vec![7, 15];
+        assert!(PlayoffService::seed(&format, &table).is_none());
+    }
+
+    fn get_season_games(prefix: &str, nr_teams: u8, league: League) -> Vec<ApiGame> {
+        let teams: Vec<String> = (1..=nr_teams).map(|e| format!("{prefix}{e:02}")).collect();
+        let mut games = vec![];
+        for (i, team1) in teams.iter().enumerate() {
+            for team2 in teams.iter().skip(i + 1) {
+                games.push(ApiGame { game_type: GameType::Season, league: league.clone(), ..get_played_game("game_uuid", team1, team2) });
+            }
+        }
+        games
+    }
+
     pub fn get_played_game(game_uuid: &str, team1: &str, team2: &str) -> ApiGame {
         ApiGame {
             game_uuid: game_uuid.to_string(),
@@ -206,7 +616,7 @@ mod tests {
             played: true,
             game_type: crate::models::GameType::PlayOff,
             league: crate::models::League::SHL,
-            season: crate::models::Season::Season2023,
+            season: crate::models::Season(2023),
             gametime: None,
             votes: None,
         }
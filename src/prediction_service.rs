@@ -0,0 +1,223 @@
+use std::{mem::discriminant, sync::Arc};
+
+use tokio::sync::RwLock;
+use tracing::log;
+
+use crate::{db::Db, player_service::PlayerService, api_player_stats_service::{ApiPlayerStatsService, TeamSeasonKey}, models_api::{game::ApiGame, athlete::ApiAthlete, event::{ApiGameEvent, ApiEventType}, pickem::{Prediction, PredictionKind}}};
+
+const EXACT_SCORE_POINTS: u32 = 5;
+const FIRST_GOAL_SCORER_POINTS: u32 = 3;
+const OVERTIME_POINTS: u32 = 1;
+
+/**
+ * Writes go through the lock, predictions on a game are read, changed and written back
+ */
+pub struct PredictionService {
+    db: Db<String, Vec<Prediction>>,
+}
+pub type SafePredictionService = Arc<RwLock<PredictionService>>;
+impl PredictionService {
+    pub fn new() -> SafePredictionService {
+        Arc::new(RwLock::new(PredictionService { db: PredictionService::get_db() }))
+    }
+
+    /**
+     * Replaces any earlier prediction of the same kind by the user on the game, kind is validated with validate
+     */
+    pub fn predict(&mut self, game: &ApiGame, user_id: &str, kind: PredictionKind) -> Prediction {
+        let mut predictions = self.db.read(&game.game_uuid).unwrap_or_default();
+        predictions.retain(|e| !(e.user_id == user_id && discriminant(&e.kind) == discriminant(&kind)));
+        let prediction = Prediction {
+            user_id: user_id.to_string(),
+            game_uuid: game.game_uuid.clone(),
+            kind,
+            correct: None,
+            points: 0,
+        };
+        predictions.push(prediction.clone());
+        _ = self.db.write(&game.game_uuid, &predictions);
+        prediction
+    }
+
+    /**
+     * Checks the prediction against the game and fills in the scorer's team and jersey from the roster
+     */
+    pub fn validate(game: &ApiGame, kind: PredictionKind) -> Result<PredictionKind, String> {
+        match kind {
+            PredictionKind::ExactScore { home_team_result, away_team_result } if home_team_result < 0 || away_team_result < 0 => {
+                Err("Invalid score".to_string())
+            },
+            PredictionKind::FirstGoalScorer { player_id, .. } => {
+                let player = PredictionService::get_roster(game).into_iter()
+                    .filter(|e| e.position != "GK")
+                    .find(|e| e.id == player_id)
+                    .ok_or("Player not in roster".to_string())?;
+                Ok(PredictionKind::FirstGoalScorer { player_id, team_code: player.team_code, jersey: player.jersey })
+            },
+            kind => Ok(kind),
+        }
+    }
+
+    /**
+     * The game's lineup, or both teams' season rosters until the lineup is published
+     */
+    fn get_roster(game: &ApiGame) -> Vec<ApiAthlete> {
+        let teams = [&game.home_team_code, &game.away_team_code];
+        let lineup: Vec<ApiAthlete> = PlayerService::read(&game.league, &game.game_uuid).unwrap_or_default().into_iter()
+            .filter(|e| teams.contains(&&e.team_code))
+            .collect();
+        if !lineup.is_empty() {
+            return lineup;
+        }
+        let db = ApiPlayerStatsService::get_team_player_db();
+        teams.into_iter()
+            .flat_map(|team| db.read(&TeamSeasonKey(game.season.clone(), team.clone())).unwrap_or_default())
+            .collect()
+    }
+
+    /**
+     * Scores every prediction on a finished game
+     */
+    pub fn score_game(&mut self, game: &ApiGame, events: &[ApiGameEvent]) {
+        let Some(mut predictions) = self.db.read(&game.game_uuid) else {
+            return
+        };
+        let first_goal = events.iter()
+            .filter_map(|e| match &e.info {
+                ApiEventType::Goal(goal) if goal.home_team_result + goal.away_team_result == 1 => Some(goal),
+                _ => None,
+            })
+            .next();
+        for prediction in predictions.iter_mut() {
+            let (correct, points) = match &prediction.kind {
+                PredictionKind::ExactScore { home_team_result, away_team_result } => (
+                    *home_team_result == game.home_team_result && *away_team_result == game.away_team_result,
+                    EXACT_SCORE_POINTS,
+                ),
+                PredictionKind::FirstGoalScorer { team_code, jersey, .. } => (
+                    first_goal.map(|e| &e.team == team_code && e.player.as_ref().map(|p| p.jersey) == Some(*jersey)).unwrap_or(false),
+                    FIRST_GOAL_SCORER_POINTS,
+                ),
+                PredictionKind::Overtime { overtime } => (
+                    *overtime == (game.overtime || game.shootout),
+                    OVERTIME_POINTS,
+                ),
+            };
+            prediction.correct = Some(correct);
+            prediction.points = if correct { points } else { 0 };
+        }
+        _ = self.db.write(&game.game_uuid, &predictions);
+        log::info!("[PREDICTION] Scored {} predictions on {game}", predictions.len());
+    }
+
+    pub fn read(game_uuid: &str) -> Vec<Prediction> {
+        PredictionService::get_db().read(&game_uuid.to_string()).unwrap_or_default()
+    }
+
+    pub fn read_all() -> Vec<Prediction> {
+        PredictionService::get_db().stream_all().flatten().collect()
+    }
+
+    fn get_db() -> Db<String, Vec<Prediction>> {
+        Db::new("v2_predictions")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use tempdir::TempDir;
+
+    use crate::{models::{League, Season, GameType}, api_player_stats_service::{ApiPlayerStatsService, TeamSeasonKey}, models_api::{game::ApiGame, report::GameStatus, athlete::{ApiAthlete, ApiAthleteStats, ApiPlayerStats}, event::{ApiGameEvent, ApiEventType, GoalInfo, Player}, pickem::PredictionKind}};
+
+    use super::PredictionService;
+
+    #[tokio::test]
+    async fn predict_and_score() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let mut game = get_game();
+        _ = ApiPlayerStatsService::get_team_player_db().write(&TeamSeasonKey(Season(2017), "LHF".to_string()), &vec![get_athlete(100, "LHF", 17)]);
+
+        let service = PredictionService::new();
+        let mut ps = service.write().await;
+        let invalid = PredictionService::validate(&game, PredictionKind::FirstGoalScorer { player_id: 200, team_code: String::new(), jersey: 0 });
+        assert!(invalid.is_err());
+        let scorer = PredictionService::validate(&game, PredictionKind::FirstGoalScorer { player_id: 100, team_code: String::new(), jersey: 0 }).unwrap();
+        assert_eq!(scorer, PredictionKind::FirstGoalScorer { player_id: 100, team_code: "LHF".to_string(), jersey: 17 });
+        ps.predict(&game, "user1", scorer);
+        ps.predict(&game, "user1", PredictionKind::ExactScore { home_team_result: 1, away_team_result: 1 });
+        ps.predict(&game, "user1", PredictionKind::ExactScore { home_team_result: 2, away_team_result: 1 });
+        ps.predict(&game, "user2", PredictionKind::Overtime { overtime: false });
+        assert!(PredictionService::validate(&game, PredictionKind::ExactScore { home_team_result: -1, away_team_result: 1 }).is_err());
+        assert_eq!(PredictionService::read(&game.game_uuid).len(), 3);
+
+        game.home_team_result = 2;
+        game.away_team_result = 1;
+        game.overtime = true;
+        game.status = GameStatus::Finished;
+        game.played = true;
+        ps.score_game(&game, &[get_goal("LHF", 17, 1, 0), get_goal("FHC", 17, 1, 1)]);
+
+        let scored: Vec<(String, Option<bool>, u32)> = PredictionService::read(&game.game_uuid).into_iter()
+            .map(|e| (e.user_id, e.correct, e.points))
+            .collect();
+        assert_eq!(scored, vec![
+            ("user1".to_string(), Some(true), 3),
+            ("user1".to_string(), Some(true), 5),
+            ("user2".to_string(), Some(false), 0),
+        ]);
+    }
+
+    fn get_goal(team: &str, jersey: i32, home_team_result: i16, away_team_result: i16) -> ApiGameEvent {
+        ApiGameEvent {
+            game_uuid: "prediction_game1".to_string(),
+            event_id: format!("{home_team_result}-{away_team_result}"),
+            revision: 1,
+            status: GameStatus::Period1,
+            gametime: "10:00".to_string(),
+            description: String::new(),
+            info: ApiEventType::Goal(GoalInfo {
+                team: team.to_string(),
+                player: Some(Player { id: None, first_name: "A".to_string(), family_name: "B".to_string(), jersey }),
+                team_advantage: "EQ".to_string(),
+                home_team_result,
+                away_team_result,
+                location: None,
+            }),
+        }
+    }
+
+    fn get_athlete(id: i32, team_code: &str, jersey: i32) -> ApiAthlete {
+        ApiAthlete {
+            id,
+            first_name: "A".to_string(),
+            family_name: "B".to_string(),
+            jersey,
+            team_code: team_code.to_string(),
+            position: "CE".to_string(),
+            season: Season(2017),
+            league: League::SHL,
+            stats: ApiAthleteStats::Player(ApiPlayerStats::default()),
+        }
+    }
+
+    fn get_game() -> ApiGame {
+        ApiGame {
+            game_uuid: "prediction_game1".to_string(),
+            home_team_code: "LHF".to_string(),
+            away_team_code: "FHC".to_string(),
+            home_team_result: 0,
+            away_team_result: 0,
+            start_date_time: Utc::now(),
+            status: GameStatus::Coming,
+            shootout: false,
+            overtime: false,
+            played: false,
+            game_type: GameType::Season,
+            league: League::SHL,
+            season: Season(2017),
+            gametime: None,
+            votes: None,
+        }
+    }
+}
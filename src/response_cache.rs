@@ -0,0 +1,118 @@
+use std::{collections::{HashMap, hash_map::DefaultHasher}, sync::{Arc, RwLock, atomic::{AtomicU64, Ordering}}, time::SystemTime, hash::{Hash, Hasher}, io::Write};
+
+use axum::body::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use lazy_static::lazy_static;
+use tracing::log;
+
+lazy_static! {
+    static ref RESPONSES: RwLock<HashMap<String, Arc<CachedResponse>>> = RwLock::new(HashMap::new());
+}
+// bumped on every invalidation, so a read racing a write never stores the old file
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Serialized response body with its ETag and, when cached, a gzipped copy
+ */
+pub struct CachedResponse {
+    pub body: Bytes,
+    pub etag: String,
+    pub gzip: Option<(Bytes, String)>, // body and ETag of the gzip representation
+    pub last_modified: Option<SystemTime>,
+}
+
+impl CachedResponse {
+    pub fn new(body: String, last_modified: Option<SystemTime>) -> Self {
+        let etag = CachedResponse::get_etag(&body);
+        CachedResponse { body: Bytes::from(body), etag, gzip: None, last_modified }
+    }
+
+    pub fn compressed(body: String, last_modified: Option<SystemTime>) -> Self {
+        let mut response = CachedResponse::new(body, last_modified);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        response.gzip = encoder.write_all(&response.body)
+            .and_then(|_| encoder.finish())
+            .map(|e| (Bytes::from(e), format!("{}-gz\"", response.etag.trim_end_matches('"'))))
+            .ok();
+        response
+    }
+
+    fn get_etag(body: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        format!("\"{:016x}-{:x}\"", hasher.finish(), body.len())
+    }
+}
+
+pub struct ResponseCache;
+
+impl ResponseCache {
+    /**
+     * Owning services load through here and invalidate the key after they write
+     */
+    pub fn get_or_load<F: FnOnce() -> (String, Option<SystemTime>)>(key: &str, load: F) -> Arc<CachedResponse> {
+        if let Some(e) = RESPONSES.read().unwrap().get(key) {
+            return e.clone();
+        }
+        let generation = GENERATION.load(Ordering::SeqCst);
+        let (body, last_modified) = load();
+        let response = Arc::new(CachedResponse::compressed(body, last_modified));
+        // nothing stored yet, don't fill the cache with misses
+        if response.body.is_empty() {
+            return response;
+        }
+        let mut responses = RESPONSES.write().unwrap();
+        if GENERATION.load(Ordering::SeqCst) == generation {
+            log::debug!("[CACHE] Stored {key}");
+            responses.insert(key.to_string(), response.clone());
+        }
+        response
+    }
+
+    pub fn invalidate(key: &str) {
+        let mut responses = RESPONSES.write().unwrap();
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        responses.remove(key);
+    }
+
+    pub fn invalidate_prefix(prefix: &str) {
+        let mut responses = RESPONSES.write().unwrap();
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        responses.retain(|key, _| !key.starts_with(prefix));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use crate::response_cache::ResponseCache;
+
+    #[test]
+    fn load_and_invalidate() {
+        let key = "test/load_and_invalidate";
+        let first = ResponseCache::get_or_load(key, || ("[1]".to_string(), None));
+        let cached = ResponseCache::get_or_load(key, || panic!("should be cached"));
+        assert_eq!(cached.body, first.body);
+        assert_eq!(cached.etag, first.etag);
+
+        let (gzip, gzip_etag) = cached.gzip.clone().unwrap();
+        let mut decoded = String::new();
+        GzDecoder::new(&gzip[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, "[1]");
+        assert_ne!(gzip_etag, cached.etag);
+
+        ResponseCache::invalidate_prefix("test/load_");
+        let reloaded = ResponseCache::get_or_load(key, || ("[1,2]".to_string(), None));
+        assert_eq!(&reloaded.body[..], b"[1,2]");
+        assert_ne!(reloaded.etag, first.etag);
+
+        ResponseCache::invalidate(key);
+        let missing = ResponseCache::get_or_load(key, || (String::new(), None));
+        assert!(missing.body.is_empty());
+        let loaded = ResponseCache::get_or_load(key, || ("[3]".to_string(), None));
+        assert_eq!(&loaded.body[..], b"[3]");
+    }
+}
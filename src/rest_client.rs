@@ -4,21 +4,13 @@ use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tracing::log;
 use crate::models_external::event::LiveEvent;
-use crate::{LogResult, CONFIG};
+use crate::LogResult;
 use crate::db::Db;
-use crate::models::{League, GameType, Season, SeasonKey};
+use crate::models::{League, GameType, SeasonKey, LeagueRegistry};
 
 pub trait IdentifiableEnum {
     fn get_uuid(&self) -> &str;
 }
-impl IdentifiableEnum for League {
-    fn get_uuid(&self) -> &str {
-        match self {
-            League::SHL => "qQ9-bb0bzEWUk",
-            League::HA => "qQ9-594cW8OWD",
-        }
-    }
-}
 
 impl IdentifiableEnum for GameType {
     fn get_uuid(&self) -> &str {
@@ -30,50 +22,37 @@ impl IdentifiableEnum for GameType {
     }
 }
 
-impl IdentifiableEnum for Season {
-    fn get_uuid(&self) -> &str {
-        match self {
-            Season::Season2023 => "qcz-3NvSZ2Cmh",
-            Season::Season2022 => "qbN-XMFfjGVt",
-            Season::Season2021 => "qZl-8qa6OaFXf",
-            Season::Season2020 => "qY7-AdVh5z1XJ",
-            Season::Season2019 => "qWX-334j11U5o1",
-            Season::Season2018 => "qUv-YXiuQN45",
-        }
-    }
-}
-
 pub fn get_season_url(key: &SeasonKey) -> String {
     let season_param = format!("seasonUuid={}", key.0.get_uuid());
-    let league_param = format!("seriesUuid={}", key.1.get_uuid());
+    let league_param = format!("seriesUuid={}", LeagueRegistry::get(&key.1).map(|e| e.uuid).unwrap_or_default());
     let game_type_param = format!("gameTypeUuid={}", key.2.get_uuid());
-    format!("{}/sports/game-info?gamePlace=all&played=all&{season_param}&{league_param}&{game_type_param}", CONFIG.get_url(&key.1))
+    format!("{}/sports/game-info?gamePlace=all&played=all&{season_param}&{league_param}&{game_type_param}", LeagueRegistry::get_url(&key.1))
 }
 
-pub async fn get_events(game_uuid: &str) -> Option<Vec<crate::models_external::event::PlayByPlay>> {
-    let url = format!("{}/gameday/play-by-play/initial-events/{game_uuid}", CONFIG.get_url(&League::SHL));
+pub async fn get_events(league: &League, game_uuid: &str) -> Option<Vec<crate::models_external::event::PlayByPlay>> {
+    let url = format!("{}/gameday/play-by-play/initial-events/{game_uuid}", LeagueRegistry::get_url(league));
     get_call(&url).await
 }
 
-pub async fn get_events_2023(game_uuid: &str) -> Option<Vec<LiveEvent>> {
-    let url = format!("{}/gameday/play-by-play/{game_uuid}", CONFIG.get_url(&League::SHL));
+pub async fn get_events_2023(league: &League, game_uuid: &str) -> Option<Vec<LiveEvent>> {
+    let url = format!("{}/gameday/play-by-play/{game_uuid}", LeagueRegistry::get_url(league));
     get_call(&url).await
 }
 
 pub fn get_report_url(league: &League, game_uuid: &str) -> String {
-    format!("{}/gameday/game-overview/{game_uuid}", CONFIG.get_url(league))
+    format!("{}/gameday/game-overview/{game_uuid}", LeagueRegistry::get_url(league))
 }
 
 pub fn get_stats_url(league: &League, game_uuid: &str) -> String {
-    format!("{}/gameday/periodstats/{game_uuid}", CONFIG.get_url(league))
+    format!("{}/gameday/periodstats/{game_uuid}", LeagueRegistry::get_url(league))
 }
 
 pub fn get_team_stats_url(league: &League, game_uuid: &str) -> String {
-    format!("{}/gameday/team-stats/{game_uuid}", CONFIG.get_url(league))
+    format!("{}/gameday/team-stats/{game_uuid}", LeagueRegistry::get_url(league))
 }
 
 pub fn get_player_stats_url(league: &League, game_uuid: &str) -> String {
-    format!("{}/gameday/boxscore/{game_uuid}", CONFIG.get_url(league))
+    format!("{}/gameday/boxscore/{game_uuid}", LeagueRegistry::get_url(league))
 }
 
 pub async fn throttle_call<T: DeserializeOwned + Serialize + Clone + Default>(url: &str, throttle_s: Option<Duration>) -> Option<T> {
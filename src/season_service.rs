@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use crate::rest_client::{self};
 use crate::db::Db;
-use crate::models::{GameType, League, SeasonKey, Season};
+use crate::models::{LeagueRegistry, SeasonKey, Season};
 use crate::models_external::season::SeasonRsp;
 
 pub struct SeasonService {
@@ -24,9 +24,9 @@ impl SeasonService {
         let mut updated = false;
         let db = Db::<String, SeasonRsp>::new("rest");
         
-        for league in League::get_all() {
-            for game_type in GameType::get_all() {
-                let key = SeasonKey(season.clone(), league.clone(), game_type.clone());
+        for league in LeagueRegistry::get_all() {
+            for game_type in league.game_types {
+                let key = SeasonKey(season.clone(), league.code.clone(), game_type.clone());
                 let url = rest_client::get_season_url(&key);
                 if db.is_stale(&url, season.get_throttle()) {
                     if let Some(obj) = rest_client::throttle_call(&url, season.get_throttle()).await {
@@ -19,7 +19,7 @@ impl FileAppend {
         log::info!("create apth {:?}", path);
         let file = OpenOptions::new()
             .create(true)
-            .write(true).append(true).open(path)
+            .append(true).open(path)
             .ok_log("could not create FileAppend");
         FileAppend {
             file,
@@ -27,8 +27,8 @@ impl FileAppend {
     }
 
     fn append(&mut self, val: &str) {
-        if self.file.is_some() && !val.is_empty() {
-            writeln!(self.file.as_ref().unwrap(), "{}", val)
+        if let Some(file) = self.file.as_mut().filter(|_| !val.is_empty()) {
+            writeln!(file, "{}", val)
                 .ok_log("Failed appending to file");
         }
     }
@@ -39,7 +39,7 @@ pub enum SseMsg {
     GameTime(models_external::event::GameTime),
     Event(models_external::event::PlayByPlay),
     LiveEvent(LiveEvent),
-    TeamStats(TeamStatistics),
+    TeamStats(#[allow(dead_code)] TeamStatistics),
     LiveState(LiveStateEvent),
 }
 pub struct SseClient;
@@ -1,27 +1,76 @@
-use std::{fmt::Display, collections::HashMap, time::Instant};
+use std::{fmt::Display, collections::{HashMap, BTreeMap}, sync::Arc, time::Instant};
+
+use chrono::NaiveDate;
 
 use serde::{Deserialize, Serialize};
 use tracing::log;
 
-use crate::{db::Db, models::{League, Season, GameType}, models_api::{game::ApiGame, standings::{Standing, Standings, TeamCode}}};
+use crate::{db::Db, response_cache::{ResponseCache, CachedResponse}, models::{League, Season, GameType, PlayoffFormat, PlayoffFormatRegistry}, models_api::{game::ApiGame, standings::{Standing, Standings, TeamCode, GameResult, StandingSplit, StandingsSnapshot, RankPoint, StandingsTrajectory, Clinch, LiveStanding, LiveStandings}, report::GameStatus}};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub struct StandingKey (pub Season);
 impl Display for StandingKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.0)
+        write!(f, "{}", self.0)
+    }
+}
+
+impl StandingSplit {
+    pub fn add_game(&mut self, result: &GameResult, gf: u16, ga: u16, points: u16) {
+        self.gp += 1;
+        self.gf += gf;
+        self.ga += ga;
+        self.points += points;
+        match result {
+            GameResult::W => self.w += 1,
+            GameResult::OTW => self.ot_w += 1,
+            GameResult::OTL => self.ot_l += 1,
+            GameResult::L => self.l += 1,
+        }
     }
 }
 
 impl Standing {
     fn add_game(&mut self, g: &ApiGame) {
-        if g.played {
-            self.gp += 1;
-            self.points += g.get_points_for(&self.team_code);
-            self.diff += g.get_goal_diff_for(&self.team_code);
+        if !g.played {
+            return
+        }
+        let result = g.get_result_for(&self.team_code);
+        let points = g.get_points_for(&self.team_code);
+        let (gf, ga) = g.get_goals_for(&self.team_code);
+        self.gp += 1;
+        self.points += points;
+        self.diff += g.get_goal_diff_for(&self.team_code);
+        self.gf += gf;
+        self.ga += ga;
+        match (&result, g.shootout) {
+            (GameResult::W, _) => self.reg_w += 1,
+            (GameResult::OTW, false) => self.ot_w += 1,
+            (GameResult::OTW, true) => self.so_w += 1,
+            (GameResult::OTL, false) => self.ot_l += 1,
+            (GameResult::OTL, true) => self.so_l += 1,
+            (GameResult::L, _) => self.reg_l += 1,
+        }
+        match result {
+            GameResult::W | GameResult::OTW => self.w += 1,
+            GameResult::OTL | GameResult::L => self.l += 1,
+        }
+        let split = match g.home_team_code == self.team_code {
+            true => &mut self.home,
+            false => &mut self.away,
+        };
+        split.add_game(&result, gf, ga, points);
+
+        self.last_five.push(result);
+        if self.last_five.len() > 5 {
+            self.last_five.remove(0);
         }
     }
+
+    fn get_tiebreak_key(&self) -> (u16, i16, u16) {
+        (self.points, self.diff, self.gf)
+    }
 }
 
 impl ApiGame {
@@ -35,14 +84,29 @@ impl ApiGame {
         };
         winner == team_code
     }
-    fn get_points_for(&self, team_code: &str) -> u16 {
+    pub fn get_result_for(&self, team_code: &str) -> GameResult {
         debug_assert!(self.home_team_code == team_code || self.away_team_code == team_code);
 
         match (self.did_team_win(team_code), self.overtime || self.shootout) {
-            (true, false) => 3,
-            (true, true) => 2,
-            (false, true) => 1,
-            (false, false) => 0,
+            (true, false) => GameResult::W,
+            (true, true) => GameResult::OTW,
+            (false, true) => GameResult::OTL,
+            (false, false) => GameResult::L,
+        }
+    }
+    pub fn get_points_for(&self, team_code: &str) -> u16 {
+        match self.get_result_for(team_code) {
+            GameResult::W => 3,
+            GameResult::OTW => 2,
+            GameResult::OTL => 1,
+            GameResult::L => 0,
+        }
+    }
+    pub fn get_goals_for(&self, team_code: &str) -> (u16, u16) {
+        let (home, away) = (self.home_team_result.max(0) as u16, self.away_team_result.max(0) as u16);
+        match team_code == self.home_team_code {
+            true => (home, away),
+            false => (away, home),
         }
     }
     fn get_goal_diff_for(&self, team_code: &str) -> i16 {
@@ -63,71 +127,273 @@ impl StandingService {
         let db = StandingService::get_db();
         let before = Instant::now();
         
-        let shl_games = games.iter()
-            .filter(|e| e.game_type == GameType::Season)
-            .filter(|e| e.league == League::SHL)
-            .collect();
-    
-        let ha_games = games.iter()
-            .filter(|e| e.game_type == GameType::Season)
-            .filter(|e| e.league == League::HA)
-            .collect();
-        
+        let mut standings = Standings::default();
+        for league in League::get_all() {
+            let league_games: Vec<&ApiGame> = games.iter()
+                .filter(|e| e.game_type == GameType::Season)
+                .filter(|e| e.league == league)
+                .collect();
+            let remaining: Vec<&ApiGame> = league_games.iter().filter(|e| !e.played).cloned().collect();
+            let mut table = StandingService::get_standings(league_games);
+            let format = PlayoffFormatRegistry::get(&league, season);
+            StandingService::set_clinched(&mut table, &remaining, format.as_ref());
+            standings.0.insert(league, table);
+        }
+
         let standing_key = StandingKey(season.clone());
-        let shl_standings = StandingService::get_standings(shl_games);
-        let ha_standings = StandingService::get_standings(ha_games);
-        _ = db.write(&standing_key, &Standings { SHL: shl_standings, HA: ha_standings });
+        _ = db.write(&standing_key, &standings);
+        ResponseCache::invalidate(&format!("standings/{}", season));
 
         log::info!("[STANDING] Updated in {:.0?}", before.elapsed());
     }
 
-    fn get_standings(games: Vec<&ApiGame>) -> Vec<Standing> {
+    /**
+     * Ordered by points, goal difference, goals scored and then by points, goal difference
+     * and goals scored in the games played between the tied teams. Teams without games last.
+     */
+    fn get_standings(mut games: Vec<&ApiGame>) -> Vec<Standing> {
+        games.sort_by_key(|e| e.start_date_time);
+        let team_map = StandingService::get_team_map(&games);
+
+        let (mut all_teams, mut not_played): (Vec<Standing>, Vec<Standing>) = team_map.into_values()
+            .partition(|e| e.gp > 0);
+
+        all_teams.sort_by(|a, b| b.get_tiebreak_key().cmp(&a.get_tiebreak_key())
+            .then_with(|| a.team_code.cmp(&b.team_code)));
+        for group in all_teams.chunk_by_mut(|a, b| a.get_tiebreak_key() == b.get_tiebreak_key()) {
+            if group.len() > 1 {
+                StandingService::sort_by_head_to_head(group, &games);
+            }
+        }
+        not_played.sort_by(|a, b| a.team_code.cmp(&b.team_code));
+
+        all_teams.into_iter().enumerate().map(|mut e| {
+            e.1.rank = u8::try_from(e.0).unwrap() + 1;
+            e.1
+        })
+        .chain(not_played)
+        .collect()
+    }
+
+    /**
+     * A team has clinched a top k spot when fewer than k other teams can reach its points,
+     * and is out of it when k other teams already have more points than it can reach
+     */
+    fn set_clinched(table: &mut [Standing], remaining: &[&ApiGame], format: Option<&PlayoffFormat>) {
+        let max_points: Vec<u16> = table.iter()
+            .map(|t| t.points + 3 * remaining.iter().filter(|e| e.home_team_code == t.team_code || e.away_team_code == t.team_code).count() as u16)
+            .collect();
+        let points: Vec<u16> = table.iter().map(|e| e.points).collect();
+        let nr_teams = table.len();
+        let clinched_top = |i: usize, k: usize| k > 0 && k < nr_teams && (0..nr_teams)
+            .filter(|j| *j != i && max_points[*j] >= points[i])
+            .count() < k;
+        let out_of_top = |i: usize, k: usize| k > 0 && k < nr_teams && (0..nr_teams)
+            .filter(|j| *j != i && points[*j] > max_points[i])
+            .count() >= k;
+
+        let direct = format.and_then(|e| e.get_direct_seeds().into_iter().max()).map(usize::from).unwrap_or_default();
+        let playoff = format.and_then(|e| e.get_direct_seeds().into_iter().chain(e.get_play_in_seeds()).max()).map(usize::from).unwrap_or_default();
+        let demotion = format.and_then(|e| e.get_demotion_ranks().into_iter().min()).map(usize::from).unwrap_or_default();
+
+        for (i, standing) in table.iter_mut().enumerate() {
+            standing.clinched = [
+                (Clinch::FirstPlace, clinched_top(i, 1)),
+                (Clinch::DirectPlayoff, clinched_top(i, direct)),
+                (Clinch::Playoff, clinched_top(i, playoff)),
+                (Clinch::Demotion, out_of_top(i, demotion.saturating_sub(1))),
+                (Clinch::Eliminated, out_of_top(i, playoff)),
+            ].into_iter()
+                .filter(|e| e.1)
+                .map(|e| e.0)
+                .collect();
+        }
+    }
+
+    fn get_team_map(games: &[&ApiGame]) -> HashMap<TeamCode, Standing> {
         let mut team_map = HashMap::<TeamCode, Standing>::new();
         for g in games {
-            {
-                team_map
-                    .entry(g.home_team_code.clone())
-                    .or_insert_with(|| Standing { team_code: g.home_team_code.clone(), rank: 0, gp: 0, points: 0, league: g.league.clone(), diff: 0 })
-                    .add_game(g);
-            } 
-            {
-                team_map
-                    .entry(g.away_team_code.clone())
-                    .or_insert_with(|| Standing { team_code: g.away_team_code.clone(), rank: 0, gp: 0, points: 0, league: g.league.clone(), diff: 0 })
-                    .add_game(g);
-            }
+            team_map
+                .entry(g.home_team_code.clone())
+                .or_insert_with(|| Standing::new(&g.home_team_code, &g.league))
+                .add_game(g);
+            team_map
+                .entry(g.away_team_code.clone())
+                .or_insert_with(|| Standing::new(&g.away_team_code, &g.league))
+                .add_game(g);
         }
+        team_map
+    }
 
-        let mut all_teams: Vec<Standing> = team_map.values().cloned().collect();
+    fn sort_by_head_to_head(group: &mut [Standing], games: &[&ApiGame]) {
+        let teams: Vec<&str> = group.iter().map(|e| e.team_code.as_str()).collect();
+        let h2h_games: Vec<&ApiGame> = games.iter()
+            .filter(|e| teams.contains(&e.home_team_code.as_str()) && teams.contains(&e.away_team_code.as_str()))
+            .cloned()
+            .collect();
+        let h2h = StandingService::get_team_map(&h2h_games);
+        let get_key = |team_code: &str| h2h.get(team_code).map(|e| e.get_tiebreak_key()).unwrap_or_default();
 
-        all_teams.sort_by(|a, b| {
-            if a.gp == 0 && b.gp == 0 {
-                a.team_code.partial_cmp(&b.team_code).unwrap()
-            } else if a.gp == 0 || b.gp == 0 {
-                b.gp.partial_cmp(&a.gp).unwrap()
-            } else if a.points == b.points {
-                if a.diff == b.diff {
-                    a.team_code.partial_cmp(&b.team_code).unwrap()
-                } else {
-                    b.diff.partial_cmp(&a.diff).unwrap()
-                }
-            } else {
-                b.points.partial_cmp(&a.points).unwrap() 
+        group.sort_by(|a, b| get_key(&b.team_code).cmp(&get_key(&a.team_code))
+            .then_with(|| a.team_code.cmp(&b.team_code)));
+    }
+
+
+    /**
+     * Ongoing season games counted with their current score, a tied game as an overtime loss for both teams
+     */
+    pub fn get_live(games: &[ApiGame]) -> LiveStandings {
+        let is_live = |e: &ApiGame| !e.played && e.status != GameStatus::Coming && e.status != GameStatus::Finished;
+        let mut live_standings = LiveStandings::default();
+        for league in League::get_all() {
+            let league_games: Vec<&ApiGame> = games.iter()
+                .filter(|e| e.game_type == GameType::Season)
+                .filter(|e| e.league == league)
+                .collect();
+            let projected: Vec<ApiGame> = league_games.iter()
+                .map(|e| match is_live(e) {
+                    true => ApiGame { played: true, overtime: e.overtime || e.home_team_result == e.away_team_result, ..(*e).clone() },
+                    false => (*e).clone(),
+                })
+                .collect();
+            let live_teams: Vec<&str> = league_games.iter()
+                .filter(|e| is_live(e))
+                .flat_map(|e| [e.home_team_code.as_str(), e.away_team_code.as_str()])
+                .collect();
+
+            let official = StandingService::get_standings(league_games.clone());
+            let table = StandingService::get_standings(projected.iter().collect()).into_iter().enumerate()
+                .map(|(i, standing)| {
+                    let official_position = official.iter().position(|e| e.team_code == standing.team_code).unwrap_or(i);
+                    LiveStanding {
+                        rank_change: official_position as i16 - i as i16,
+                        live: live_teams.contains(&standing.team_code.as_str()),
+                        standing,
+                    }
+                })
+                .collect();
+            live_standings.0.insert(league, table);
+        }
+        live_standings
+    }
+
+    /**
+     * Rebuild the table after every played season game, oldest first. Skipped when the stored history already has a snapshot per played game
+     */
+    pub fn update_history(season: &Season, games: &[ApiGame]) {
+        let before = Instant::now();
+        let per_league: Vec<Vec<&ApiGame>> = League::get_all().iter()
+            .map(|league| StandingService::get_history_games(games, league))
+            .collect();
+        let played = per_league.iter().flatten().filter(|e| e.played).count();
+        let stored = StandingService::get_history_db().read(&StandingKey(season.clone()));
+        if stored.map(|e| e.len()) == Some(played) {
+            return;
+        }
+        let mut history = vec![];
+        for league_games in per_league {
+            for (i, _) in league_games.iter().enumerate().filter(|e| e.1.played) {
+                history.push(StandingService::get_snapshot(&league_games, i));
             }
-        });
+        }
+        history.sort_by_key(|e| e.date);
+        _ = StandingService::get_history_db().write(&StandingKey(season.clone()), &history);
+        log::info!("[STANDING] Updated history {} snapshots in {:.0?}", history.len(), before.elapsed());
+    }
 
-        all_teams.into_iter().enumerate().map(|mut e| {
-            e.1.rank = match e.1.gp {
-                0 => 0,
-                _ => u8::try_from(e.0).unwrap() + 1,
-            };
-            e.1
-        }).collect()
+    /**
+     * Store the table right after the given game, replacing any earlier snapshot of it
+     */
+    pub fn add_snapshot(season: &Season, games: &[ApiGame], game_uuid: &str) {
+        let Some(game) = games.iter().find(|e| e.game_uuid == game_uuid && e.played && e.game_type == GameType::Season) else {
+            return
+        };
+        let league_games = StandingService::get_history_games(games, &game.league);
+        let Some(i) = league_games.iter().position(|e| e.game_uuid == game_uuid) else {
+            return
+        };
+        let db = StandingService::get_history_db();
+        let key = StandingKey(season.clone());
+        let mut history = db.read(&key).unwrap_or_default();
+        history.retain(|e| e.game_uuid != game_uuid);
+        history.push(StandingService::get_snapshot(&league_games, i));
+        history.sort_by_key(|e| e.date);
+        _ = db.write(&key, &history);
+        log::info!("[STANDING] Added snapshot after {game_uuid}");
+    }
+
+    fn get_history_games<'a>(games: &'a [ApiGame], league: &League) -> Vec<&'a ApiGame> {
+        let mut league_games: Vec<&ApiGame> = games.iter()
+            .filter(|e| e.game_type == GameType::Season)
+            .filter(|e| &e.league == league)
+            .collect();
+        league_games.sort_by(|a, b| a.start_date_time.cmp(&b.start_date_time).then_with(|| a.game_uuid.cmp(&b.game_uuid)));
+        league_games
     }
 
+    /**
+     * The table with only the games up until and including index i counted
+     */
+    fn get_snapshot(league_games: &[&ApiGame], i: usize) -> StandingsSnapshot {
+        let games: Vec<ApiGame> = league_games.iter().enumerate()
+            .map(|(j, e)| ApiGame { played: e.played && j <= i, ..(*e).clone() })
+            .collect();
+        let table = StandingService::get_standings(games.iter().collect());
+        StandingsSnapshot {
+            game_uuid: league_games[i].game_uuid.clone(),
+            date: league_games[i].start_date_time,
+            league: league_games[i].league.clone(),
+            round: table.iter().map(|e| e.gp).max().unwrap_or_default(),
+            table,
+        }
+    }
 
-    pub fn read_raw(season: Season) -> String {
-        StandingService::get_db().read_raw(&StandingKey(season))
+    pub fn read_history(season: &Season) -> Vec<StandingsSnapshot> {
+        StandingService::get_history_db().read(&StandingKey(season.clone())).unwrap_or_default()
+    }
+
+    /**
+     * The latest table on the given date and/or after the given round, per league
+     */
+    pub fn read_as_of(season: &Season, date: Option<NaiveDate>, round: Option<u16>) -> Standings {
+        let history = StandingService::read_history(season);
+        let mut standings = Standings::default();
+        for league in League::get_all() {
+            let table = history.iter()
+                .rfind(|e| e.league == league
+                    && date.map(|d| e.date.date_naive() <= d).unwrap_or(true)
+                    && round.map(|r| e.round <= r).unwrap_or(true))
+                .map(|e| e.table.clone())
+                .unwrap_or_default();
+            standings.0.insert(league, table);
+        }
+        standings
+    }
+
+    /**
+     * Every team's rank after each snapshot where its rank or games played changed
+     */
+    pub fn read_trajectory(season: &Season) -> StandingsTrajectory {
+        let mut trajectory = StandingsTrajectory::new();
+        for snapshot in StandingService::read_history(season) {
+            let teams: &mut BTreeMap<TeamCode, Vec<RankPoint>> = trajectory.entry(snapshot.league.clone()).or_default();
+            for standing in snapshot.table.iter().filter(|e| e.gp > 0) {
+                let points = teams.entry(standing.team_code.clone()).or_default();
+                let changed = points.last().map(|e| e.rank != standing.rank || e.gp != standing.gp).unwrap_or(true);
+                if changed {
+                    points.push(RankPoint { date: snapshot.date, gp: standing.gp, rank: standing.rank, points: standing.points });
+                }
+            }
+        }
+        trajectory
+    }
+
+    pub fn read_cached(season: Season) -> Arc<CachedResponse> {
+        ResponseCache::get_or_load(&format!("standings/{}", season), || {
+            let db = StandingService::get_db();
+            let key = StandingKey(season);
+            (db.read_raw(&key), db.last_modified(&key))
+        })
     }
 
     pub fn read(season: Season) -> Option<Standings> {
@@ -137,14 +403,21 @@ impl StandingService {
     fn get_db() -> Db<StandingKey, Standings> {
         Db::new("v2_standings")
     }
+
+    fn get_history_db() -> Db<StandingKey, Vec<StandingsSnapshot>> {
+        Db::new("v2_standings_history")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
+    use chrono::{Utc, Duration};
     use tempdir::TempDir;
 
-    use crate::models_api::{game::ApiGame, report::GameStatus};
+    use crate::{models::League, models_api::{game::ApiGame, report::GameStatus}};
+
+    use crate::models::{PlayoffFormatRegistry, Season};
+    use crate::models_api::standings::{GameResult, Clinch, Standing};
 
     use super::StandingService;
 
@@ -158,35 +431,134 @@ mod tests {
             get_coming_game("game_uuid124", "TIK", "MODO"),
         ];
 
-        StandingService::update(&crate::models::Season::Season2023, &games);
+        StandingService::update(&crate::models::Season(2023), &games);
 
-        let standings = StandingService::read(crate::models::Season::Season2023).unwrap();
-        assert_eq!(standings.SHL.len(), 4);
-        assert_eq!(standings.HA.len(), 0);
+        let standings = StandingService::read(crate::models::Season(2023)).unwrap();
+        assert_eq!(standings.get(&League::SHL).len(), 4);
+        assert_eq!(standings.get(&League::HA).len(), 0);
 
-        let lhf = standings.SHL.iter().find(|e| e.team_code == "LHF").unwrap();
+        let lhf = standings.get(&League::SHL).iter().find(|e| e.team_code == "LHF").unwrap();
         assert_eq!(lhf.gp, 1);
         assert_eq!(lhf.rank, 1);
         assert_eq!(lhf.points, 3);
 
-        let fhc = standings.SHL.iter().find(|e| e.team_code == "FHC").unwrap();
+        let fhc = standings.get(&League::SHL).iter().find(|e| e.team_code == "FHC").unwrap();
         assert_eq!(fhc.gp, 1);
         assert_eq!(fhc.rank, 2);
         assert_eq!(fhc.points, 0);
 
-        let tik = standings.SHL.iter().find(|e| e.team_code == "TIK").unwrap();
+        let tik = standings.get(&League::SHL).iter().find(|e| e.team_code == "TIK").unwrap();
         assert_eq!(tik.gp, 0);
         assert_eq!(tik.rank, 0);
         assert_eq!(tik.points, 0);
 
-        let modo = standings.SHL.iter().find(|e| e.team_code == "MODO").unwrap();
+        let modo = standings.get(&League::SHL).iter().find(|e| e.team_code == "MODO").unwrap();
         assert_eq!(modo.gp, 0);
         assert_eq!(modo.rank, 0);
         assert_eq!(modo.points, 0);
 
         // if gp == 0, alphabetic order
-        assert_eq!(standings.SHL[2].team_code, "MODO");
-        assert_eq!(standings.SHL[3].team_code, "TIK");
+        assert_eq!(standings.get(&League::SHL)[2].team_code, "MODO");
+        assert_eq!(standings.get(&League::SHL)[3].team_code, "TIK");
+    }
+
+    #[test]
+    fn tiebreak_on_head_to_head() {
+        // LHF and FHC end up on 3 points, 0 diff and 2 goals scored, LHF won the game between them
+        let games = [
+            ApiGame { home_team_result: 2, away_team_result: 1, ..get_played_game("game_uuid1", "LHF", "FHC") },
+            ApiGame { home_team_result: 1, away_team_result: 0, ..get_played_game("game_uuid2", "FHC", "TIK") },
+            ApiGame { home_team_result: 1, away_team_result: 0, ..get_played_game("game_uuid3", "TIK", "LHF") },
+            ApiGame { home_team_result: 0, away_team_result: 1, shootout: true, ..get_played_game("game_uuid4", "MODO", "TIK") },
+        ];
+        let standings = StandingService::get_standings(games.iter().collect());
+        let order: Vec<&str> = standings.iter().map(|e| e.team_code.as_str()).collect();
+        assert_eq!(order, vec!["TIK", "LHF", "FHC", "MODO"]);
+
+        let tik = &standings[0];
+        assert_eq!((tik.gp, tik.points, tik.diff, tik.gf, tik.ga), (3, 5, 1, 2, 1));
+        assert_eq!((tik.reg_w, tik.so_w, tik.reg_l), (1, 1, 1));
+        assert_eq!(tik.away.gp, 2);
+        assert_eq!(tik.last_five, vec![GameResult::L, GameResult::W, GameResult::OTW]);
+
+        let modo = &standings[3];
+        assert_eq!((modo.points, modo.so_l, modo.rank), (1, 1, 4));
+    }
+
+    #[test]
+    fn history() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let now = Utc::now();
+        let games = vec![
+            ApiGame { start_date_time: now - Duration::days(2), ..get_played_game("game_uuid1", "LHF", "FHC") },
+            ApiGame { start_date_time: now - Duration::days(1), ..get_played_game("game_uuid2", "FHC", "LHF") },
+            ApiGame { start_date_time: now - Duration::days(1), ..get_played_game("game_uuid3", "TIK", "MODO") },
+            ApiGame { start_date_time: now + Duration::days(1), ..get_coming_game("game_uuid4", "MODO", "LHF") },
+        ];
+        let season = crate::models::Season(2019);
+        StandingService::update_history(&season, &games[..2]);
+        StandingService::add_snapshot(&season, &games, "game_uuid3");
+        StandingService::add_snapshot(&season, &games, "game_uuid4");
+        assert_eq!(StandingService::read_history(&season).len(), 3);
+
+        let first_day = StandingService::read_as_of(&season, Some((now - Duration::days(2)).date_naive()), None);
+        assert_eq!(first_day.get(&League::SHL)[0].team_code, "LHF");
+        assert_eq!(first_day.get(&League::SHL)[0].gp, 1);
+
+        let round_one = StandingService::read_as_of(&season, None, Some(1));
+        assert_eq!(round_one.get(&League::SHL)[0].team_code, "LHF");
+        assert_eq!(round_one.get(&League::SHL)[1].rank, 2);
+
+        let latest = StandingService::read_as_of(&season, None, None);
+        assert_eq!(latest.get(&League::SHL).iter().map(|e| e.gp).sum::<u16>(), 6);
+
+        let trajectory = StandingService::read_trajectory(&season);
+        let lhf: Vec<u8> = trajectory[&League::SHL]["LHF"].iter().map(|e| e.rank).collect();
+        assert_eq!(lhf, vec![1, 2, 3]);
+
+        // complete history is kept, a length mismatch is rebuilt
+        StandingService::update_history(&season, &games);
+        assert_eq!(StandingService::read_history(&season).len(), 3);
+        StandingService::update_history(&season, &games[..2]);
+        assert_eq!(StandingService::read_history(&season).len(), 2);
+    }
+
+    #[test]
+    fn clinched() {
+        let points = [100, 90, 89, 88, 87, 86, 60, 59, 58, 57, 50, 45, 20, 10];
+        let mut table: Vec<Standing> = points.iter().enumerate()
+            .map(|(i, points)| Standing { rank: i as u8 + 1, gp: 40, points: *points, ..Standing::new(&format!("T{:02}", i + 1), &League::SHL) })
+            .collect();
+        let games: Vec<ApiGame> = (0..7)
+            .map(|i| get_coming_game("game_uuid", &format!("T{:02}", i * 2 + 1), &format!("T{:02}", i * 2 + 2)))
+            .collect();
+        let remaining: Vec<&ApiGame> = games.iter().collect();
+        let format = PlayoffFormatRegistry::get(&League::SHL, &Season(2023));
+
+        StandingService::set_clinched(&mut table, &remaining, format.as_ref());
+
+        assert_eq!(table[0].clinched, vec![Clinch::FirstPlace, Clinch::DirectPlayoff, Clinch::Playoff]);
+        assert_eq!(table[5].clinched, vec![Clinch::DirectPlayoff, Clinch::Playoff]);
+        assert_eq!(table[6].clinched, vec![Clinch::Playoff]);
+        assert_eq!(table[9].clinched, vec![Clinch::Playoff]);
+        assert_eq!(table[10].clinched, vec![Clinch::Eliminated]);
+        assert_eq!(table[11].clinched, vec![Clinch::Eliminated]);
+        assert_eq!(table[12].clinched, vec![Clinch::Demotion, Clinch::Eliminated]);
+    }
+
+    #[test]
+    fn live() {
+        let games = vec![
+            get_played_game("game_uuid1", "LHF", "FHC"),
+            ApiGame { status: GameStatus::Period3, home_team_result: 1, away_team_result: 4, ..get_coming_game("game_uuid2", "TIK", "MODO") },
+            ApiGame { status: GameStatus::Period2, home_team_result: 2, away_team_result: 2, ..get_coming_game("game_uuid3", "FHC", "LHF") },
+        ];
+        let live = StandingService::get_live(&games);
+        let table = &live.0[&League::SHL];
+        let order: Vec<(&str, u16, i16, bool)> = table.iter()
+            .map(|e| (e.standing.team_code.as_str(), e.standing.points, e.rank_change, e.live))
+            .collect();
+        assert_eq!(order, vec![("LHF", 4, 0, true), ("MODO", 3, 1, true), ("FHC", 1, -1, true), ("TIK", 0, 0, true)]);
     }
 
     pub fn get_played_game(game_uuid: &str, team1: &str, team2: &str) -> ApiGame {
@@ -203,7 +575,7 @@ mod tests {
             played: true,
             game_type: crate::models::GameType::Season,
             league: crate::models::League::SHL,
-            season: crate::models::Season::Season2022,
+            season: crate::models::Season(2022),
             gametime: None,
             votes: None,
         }
@@ -222,7 +594,7 @@ mod tests {
             played: false,
             game_type: crate::models::GameType::Season,
             league: crate::models::League::SHL,
-            season: crate::models::Season::Season2022,
+            season: crate::models::Season(2022),
             gametime: None,
             votes: None,
         }
@@ -90,9 +90,9 @@ pub struct StatsService;
 impl StatsService {
 
     pub async fn update(league: &League, season: &Season, game_uuid: &str, throttle_s: Option<Duration>) -> Option<ApiGameStats> {
-        match season {
-            Season::Season2023 => StatsService::update_2023(league, game_uuid, throttle_s).await,
-            _ => StatsService::update_old(league, game_uuid, throttle_s).await,
+        match season.is_legacy_api() {
+            false => StatsService::update_2023(league, game_uuid, throttle_s).await,
+            true => StatsService::update_old(league, game_uuid, throttle_s).await,
         }
     }
 
@@ -108,6 +108,16 @@ impl StatsService {
         rsp.map(|e| e.into())
     }
 
+    /**
+     * Stored stats only, never calls the remote api
+     */
+    pub fn read(league: &League, season: &Season, game_uuid: &str) -> Option<ApiGameStats> {
+        match season.is_legacy_api() {
+            false => Db::<String, TeamStatsRsp>::new("rest").read(&rest_client::get_team_stats_url(league, game_uuid)).map(|e| e.into()),
+            true => Db::<String, StatsRsp>::new("rest").read(&rest_client::get_stats_url(league, game_uuid)).map(|e| e.into()),
+        }
+    }
+
     pub fn is_stale(league: &League, game_uuid: &str, throttle_s: Option<Duration>) -> bool {
         let url = rest_client::get_stats_url(league, game_uuid);
         let db = Db::<String, StatsRsp>::new("rest");
@@ -1,25 +1,193 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::log;
 
-use crate::db::Db;
+use crate::{db::Db, models::League};
 
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusLevel {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/**
+ * Hand-edited status files may use any level, unknown ones are shown as info rather than dropping the banner
+ */
+impl<'de> Deserialize<'de> for StatusLevel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lvl = String::deserialize(deserializer)?;
+        Ok(match lvl.to_lowercase().as_str() {
+            "error" => StatusLevel::Error,
+            "warning" | "warn" => StatusLevel::Warning,
+            _ => StatusLevel::Info,
+        })
+    }
+}
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Status {
-    msg: String,
-    lvl: String,
+    #[serde(default)]
+    pub id: String,
+    pub msg: String,
+    #[serde(default)]
+    pub lvl: StatusLevel,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub app_versions: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub leagues: Vec<League>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub expires: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct ApiStatusRsp {
-    status: Option<Status>,
+impl Status {
+    pub fn is_active(&self, now: &DateTime<Utc>) -> bool {
+        self.start.map(|e| &e <= now).unwrap_or(true) &&
+        self.expires.map(|e| &e > now).unwrap_or(true)
+    }
+
+    fn is_applicable(&self, app_version: Option<&str>, league: Option<&League>) -> bool {
+        let version_ok = match app_version {
+            Some(v) => self.app_versions.is_empty() || self.app_versions.iter().any(|e| e == v),
+            None => true,
+        };
+        let league_ok = match league {
+            Some(l) => self.leagues.is_empty() || self.leagues.contains(l),
+            None => true,
+        };
+        version_ok && league_ok
+    }
 }
 
 pub struct StatusService {
 
 }
 impl StatusService {
-    pub fn read_raw() -> String {
-        let db: Db<String, Status> = Db::new("v2_status");
-        db.read_raw(&"key".to_string())
+    pub fn read_all() -> Vec<Status> {
+        let db = StatusService::get_db();
+        match db.read(&"all".to_string()) {
+            Some(all) => all,
+            None => StatusService::read_legacy(),
+        }
+    }
+
+    /**
+     * The single hand-edited status file used before statuses were managed through the api
+     */
+    fn read_legacy() -> Vec<Status> {
+        let legacy: Db<String, Status> = Db::new("v2_status");
+        legacy.read(&"key".to_string())
+            .map(|mut e| { e.id = "key".to_string(); vec![e] })
+            .unwrap_or_default()
+    }
+
+    pub fn read_active(app_version: Option<&str>, league: Option<&League>) -> Vec<Status> {
+        let now = Utc::now();
+        let mut result: Vec<Status> = StatusService::read_all().into_iter()
+            .filter(|e| e.is_active(&now))
+            .filter(|e| e.is_applicable(app_version, league))
+            .collect();
+        result.sort_by_key(|e| match e.lvl {
+            StatusLevel::Error => 0,
+            StatusLevel::Warning => 1,
+            StatusLevel::Info => 2,
+        });
+        result
+    }
+
+    pub fn read_current(app_version: Option<&str>, league: Option<&League>) -> Option<Status> {
+        StatusService::read_active(app_version, league).into_iter().next()
+    }
+
+    pub fn upsert(mut status: Status) -> Status {
+        let mut all = StatusService::read_all();
+        if status.id.is_empty() {
+            status.id = format!("{}", Utc::now().timestamp_millis());
+        }
+        all.retain(|e| e.id != status.id);
+        all.push(status.clone());
+        log::info!("[STATUS] Upsert {} {:?} {}", status.id, status.lvl, status.msg);
+        _ = StatusService::get_db().write(&"all".to_string(), &all);
+        status
+    }
+
+    pub fn remove(id: &str) -> bool {
+        let mut all = StatusService::read_all();
+        let before = all.len();
+        all.retain(|e| e.id != id);
+        log::info!("[STATUS] Remove {id}");
+        _ = StatusService::get_db().write(&"all".to_string(), &all);
+        before != all.len()
+    }
+
+    pub fn clear() {
+        log::info!("[STATUS] Clear");
+        _ = StatusService::get_db().write(&"all".to_string(), &vec![]);
     }
-}
\ No newline at end of file
+
+    fn get_db() -> Db<String, Vec<Status>> {
+        Db::new("v2_status")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Utc, Duration};
+    use tempdir::TempDir;
+
+    use crate::models::League;
+
+    use super::{StatusService, Status, StatusLevel};
+
+    fn status(id: &str, lvl: StatusLevel) -> Status {
+        Status { id: id.to_string(), msg: "Data delayed".to_string(), lvl, app_versions: vec![], leagues: vec![], start: None, expires: None }
+    }
+
+    #[test]
+    fn sunny_day() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        StatusService::clear();
+
+        StatusService::upsert(status("info", StatusLevel::Info));
+        StatusService::upsert(Status { leagues: vec![League::HA], ..status("error", StatusLevel::Error) });
+        StatusService::upsert(Status { expires: Some(Utc::now() - Duration::minutes(1)), ..status("expired", StatusLevel::Error) });
+        StatusService::upsert(Status { start: Some(Utc::now() + Duration::minutes(1)), ..status("future", StatusLevel::Error) });
+
+        assert_eq!(StatusService::read_all().len(), 4);
+
+        let active = StatusService::read_active(None, None);
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].id, "error");
+
+        let current = StatusService::read_current(None, Some(&League::SHL)).unwrap();
+        assert_eq!(current.id, "info");
+
+        assert!(StatusService::remove("info"));
+        assert!(!StatusService::remove("info"));
+        assert!(StatusService::read_current(None, Some(&League::SHL)).is_none());
+    }
+
+    #[test]
+    fn legacy_file() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        let path = format!("{}/v2_status", crate::CONFIG.db_path);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(format!("{path}/key"), r#"{"msg": "Data delayed", "lvl": "CRITICAL"}"#).unwrap();
+
+        let legacy = StatusService::read_legacy();
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].id, "key");
+        assert_eq!(legacy[0].lvl, StatusLevel::Info);
+
+        let parse = |json: &str| serde_json::from_str::<Status>(json).unwrap().lvl;
+        assert_eq!(parse(r#"{"msg": "", "lvl": "Warning"}"#), StatusLevel::Warning);
+        assert_eq!(parse(r#"{"msg": "", "lvl": "error"}"#), StatusLevel::Error);
+        assert_eq!(parse(r#"{"msg": ""}"#), StatusLevel::Info);
+    }
+}
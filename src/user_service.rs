@@ -1,3 +1,4 @@
+use rand::{Rng, thread_rng, distributions::Alphanumeric};
 use serde::{Serialize, Deserialize};
 use tracing::log;
 
@@ -23,8 +24,14 @@ pub struct User {
     pub explicit_games: Vec<String>,
     #[serde(default)]
     pub live_activities: Vec<LiveActivityEntry>,
+    #[serde(default)]
+    pub vote_results: Option<bool>,
+    #[serde(default)]
+    pub calendar_token: Option<String>, // secret part of the calendar url, the user id alone is not
 }
 
+const CALENDAR_TOKEN_LEN: usize = 32;
+
 pub struct UserService;
 
 impl UserService {
@@ -48,6 +55,9 @@ impl UserService {
                 user.apn_token = request.apn_token;
                 user.ios_version = request.ios_version;
                 user.app_version = request.app_version;
+                if request.vote_results.is_some() {
+                    user.vote_results = request.vote_results; // older apps don't send it
+                }
                 user
             },
             None => {
@@ -57,6 +67,7 @@ impl UserService {
                     apn_token: request.apn_token,
                     ios_version: request.ios_version,
                     app_version: request.app_version,
+                    vote_results: request.vote_results,
                     ..Default::default()
                 }
             }
@@ -101,6 +112,37 @@ impl UserService {
         }
     }
 
+    pub fn read(user_id: &str) -> Option<User> {
+        UserService::get_db().read(&user_id.to_string())
+    }
+
+    /**
+     * The user's calendar token, created on the first request and then kept so the calendar url stays the same
+     */
+    pub fn get_calendar_token(user_id: &str) -> Option<String> {
+        let db = UserService::get_db();
+        let mut user = db.read(&user_id.to_string())?;
+        if let Some(token) = &user.calendar_token {
+            return Some(token.clone());
+        }
+        let token: String = thread_rng().sample_iter(&Alphanumeric).take(CALENDAR_TOKEN_LEN).map(char::from).collect();
+        user.calendar_token = Some(token.clone());
+        _ = db.write(&user.id, &user);
+        log::info!("[USER] Created calendar token {user_id}");
+        Some(token)
+    }
+
+    /**
+     * The user, if the token matches the one handed out by get_calendar_token
+     */
+    pub fn read_by_calendar_token(user_id: &str, token: &str) -> Option<User> {
+        UserService::read(user_id).filter(|e| e.calendar_token.as_deref() == Some(token))
+    }
+
+    pub fn exists(user_id: &str) -> bool {
+        UserService::get_db().read(&user_id.to_string()).is_some()
+    }
+
     pub fn remove_apn_token(user_id: &str) {
         log::info!("[USER] Remove apn_token {user_id}");
         let db = UserService::get_db();
@@ -113,4 +155,30 @@ impl UserService {
     fn get_db() -> Db<String, User> {
         Db::new("v2_user")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use crate::models_api::user::AddUser;
+
+    use super::UserService;
+
+    #[test]
+    fn calendar_token() {
+        std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
+        assert!(UserService::get_calendar_token("calendar_user").is_none());
+        UserService::handle(AddUser { id: "calendar_user".to_string(), teams: vec!["LHF".to_string()], apn_token: None, ios_version: None, app_version: None, vote_results: None });
+
+        let token = UserService::get_calendar_token("calendar_user").unwrap();
+        assert_eq!(token.len(), 32);
+        assert_eq!(UserService::get_calendar_token("calendar_user"), Some(token.clone()));
+        assert!(UserService::read_by_calendar_token("calendar_user", &token).is_some());
+        assert!(UserService::read_by_calendar_token("calendar_user", "nope").is_none());
+        assert!(UserService::read_by_calendar_token("other_user", &token).is_none());
+
+        // updating the user keeps the token
+        UserService::handle(AddUser { id: "calendar_user".to_string(), teams: vec!["FHC".to_string()], apn_token: None, ios_version: None, app_version: None, vote_results: None });
+        assert_eq!(UserService::read_by_calendar_token("calendar_user", &token).unwrap().teams, vec!["FHC"]);
+    }
+}
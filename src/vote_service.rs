@@ -1,9 +1,17 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::{HashMap, HashSet, VecDeque}, sync::Arc, time::{Duration, Instant}};
 
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use tokio::sync::{RwLock, mpsc::Sender};
+use tracing::log;
 
-use crate::{db::Db, models_api::vote::VotePerGame};
+use crate::{db::Db, models_api::vote::{VotePerGame, VoteAnomalies}};
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+const MAX_VOTES_PER_USER: usize = 30;
+const MAX_VOTES_PER_IP: usize = 300; // carrier NAT puts many users behind one address
+const BURST_WINDOW_S: i64 = 60;
+const BURST_NR_VOTES: usize = 40; // more than both leagues play in a round
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Vote {
@@ -11,14 +19,55 @@ pub struct Vote {
     pub game_uuid: String,
     pub team_code: String,
     pub is_home_winner: bool, // is home team picked as winner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
 }
 
+/**
+ * Sliding window of hits per key
+ */
+struct RateLimiter {
+    max: usize,
+    hits: HashMap<String, VecDeque<Instant>>,
+    last_sweep: Option<Instant>,
+}
+impl RateLimiter {
+    fn new(max: usize) -> RateLimiter {
+        RateLimiter { max, hits: HashMap::new(), last_sweep: None }
+    }
+
+    fn is_limited(&mut self, key: &str, now: Instant) -> bool {
+        self.sweep(now);
+        let hits = self.hits.entry(key.to_string()).or_default();
+        while hits.front().is_some_and(|e| now.duration_since(*e) > RATE_LIMIT_WINDOW) {
+            hits.pop_front();
+        }
+        if hits.len() >= self.max {
+            return true;
+        }
+        hits.push_back(now);
+        false
+    }
+
+    /**
+     * Once per window, forget keys without hits inside it so the map only holds recent callers
+     */
+    fn sweep(&mut self, now: Instant) {
+        if self.last_sweep.is_some_and(|e| now.duration_since(e) <= RATE_LIMIT_WINDOW) {
+            return;
+        }
+        self.hits.retain(|_, hits| hits.back().is_some_and(|e| now.duration_since(*e) <= RATE_LIMIT_WINDOW));
+        self.last_sweep = Some(now);
+    }
+}
 
 
 pub struct VoteService {
     db: Db<String, Vec<Vote>>,
     in_mem_per_game: HashMap<String, VotePerGame>,
     on_vote: Sender<(String, VotePerGame)>,
+    user_limiter: RateLimiter,
+    ip_limiter: RateLimiter,
 }
 pub type SafeVoteService = Arc<RwLock<VoteService>>;
 impl VoteService {
@@ -27,7 +76,23 @@ impl VoteService {
     ) -> SafeVoteService {
         let db = Db::new("v2_votes"); 
         let in_mem_per_game = VoteService::generate_per_game(&db.read(&"all".to_string()).unwrap_or_default());
-        Arc::new(RwLock::new(VoteService { db, in_mem_per_game, on_vote }))
+        Arc::new(RwLock::new(VoteService {
+            db,
+            in_mem_per_game,
+            on_vote,
+            user_limiter: RateLimiter::new(MAX_VOTES_PER_USER),
+            ip_limiter: RateLimiter::new(MAX_VOTES_PER_IP),
+        }))
+    }
+
+    pub fn is_rate_limited(&mut self, user_id: &str, ip: Option<&str>) -> bool {
+        let now = Instant::now();
+        let user_limited = self.user_limiter.is_limited(user_id, now);
+        let ip_limited = ip.map(|ip| self.ip_limiter.is_limited(ip, now)).unwrap_or(false);
+        if user_limited || ip_limited {
+            log::warn!("[VOTE] Rate limited {user_id} {}", ip.unwrap_or_default());
+        }
+        user_limited || ip_limited
     }
 
     pub async fn vote(&mut self, vote: Vote) -> VotePerGame {
@@ -48,6 +113,82 @@ impl VoteService {
         result
     }
 
+    pub fn read_votes() -> Vec<Vote> {
+        let db: Db<String, Vec<Vote>> = Db::new("v2_votes");
+        db.read(&"all".to_string()).unwrap_or_default()
+    }
+
+    /**
+     * Vote split per game, counting only the given users
+     */
+    pub fn read_per_game_for(user_ids: &[String]) -> HashMap<String, VotePerGame> {
+        let votes: Vec<Vote> = VoteService::read_votes().into_iter()
+            .filter(|e| user_ids.contains(&e.user_id))
+            .collect();
+        VoteService::generate_per_game(&votes)
+    }
+
+    /**
+     * Votes by users that are not registered, and users voting in bursts no app user would
+     */
+    pub fn find_anomalies(&self, is_registered: impl Fn(&str) -> bool) -> VoteAnomalies {
+        VoteService::get_anomalies(&self.db.read(&"all".to_string()).unwrap_or_default(), is_registered)
+    }
+
+    fn get_anomalies(votes: &[Vote], is_registered: impl Fn(&str) -> bool) -> VoteAnomalies {
+        let mut votes_per_user: HashMap<&str, Vec<&Vote>> = HashMap::new();
+        for vote in votes {
+            votes_per_user.entry(vote.user_id.as_str()).or_default().push(vote);
+        }
+
+        let mut unknown_users = vec![];
+        let mut burst_users = vec![];
+        for (user_id, votes) in votes_per_user {
+            if !is_registered(user_id) {
+                unknown_users.push(user_id.to_string());
+            } else if VoteService::has_burst(&votes) {
+                burst_users.push(user_id.to_string());
+            }
+        }
+        unknown_users.sort();
+        burst_users.sort();
+        let user_ids: HashSet<&String> = unknown_users.iter().chain(burst_users.iter()).collect();
+        VoteAnomalies {
+            nr_votes: votes.iter().filter(|e| user_ids.contains(&e.user_id)).count(),
+            unknown_users,
+            burst_users,
+        }
+    }
+
+    fn has_burst(votes: &[&Vote]) -> bool {
+        let mut created: Vec<DateTime<Utc>> = votes.iter().filter_map(|e| e.created).collect();
+        created.sort();
+        created.windows(BURST_NR_VOTES).any(|e| (e[BURST_NR_VOTES - 1] - e[0]).num_seconds() < BURST_WINDOW_S)
+    }
+
+    /**
+     * Removes all votes by the users and recomputes the aggregates of the affected games
+     */
+    pub async fn purge(&mut self, user_ids: &[String]) -> usize {
+        let mut all_votes = self.db.read(&"all".to_string()).unwrap_or_default();
+        let affected_games: HashSet<String> = all_votes.iter()
+            .filter(|e| user_ids.contains(&e.user_id))
+            .map(|e| e.game_uuid.clone())
+            .collect();
+        let nr_before = all_votes.len();
+        all_votes.retain(|e| !user_ids.contains(&e.user_id));
+        _ = self.db.write(&"all".to_string(), &all_votes);
+        self.in_mem_per_game = VoteService::generate_per_game(&all_votes);
+
+        for game_uuid in affected_games {
+            let vote_per_game = self.in_mem_per_game.get(&game_uuid).copied().unwrap_or_default();
+            _ = self.on_vote.send((game_uuid, vote_per_game)).await;
+        }
+        let nr_purged = nr_before - all_votes.len();
+        log::info!("[VOTE] Purged {nr_purged} votes by {} users", user_ids.len());
+        nr_purged
+    }
+
     pub fn get_all(&self) -> HashMap<String, VotePerGame> {
         self.in_mem_per_game.clone()
     }
@@ -79,11 +220,14 @@ impl VoteService {
 
 #[cfg(test)]
 mod tests{
+    use std::time::Instant;
+
+    use chrono::{DateTime, Duration, Utc};
     use tempdir::TempDir;
 
     use crate::vote_service::Vote;
 
-    use super::VoteService;
+    use super::{VoteService, RateLimiter, MAX_VOTES_PER_USER, RATE_LIMIT_WINDOW};
 
     fn before() {
         std::env::set_var("DB_PATH", TempDir::new("test").expect("dir to be created").path().to_str().unwrap());
@@ -95,8 +239,8 @@ mod tests{
         before();
         let (sender, _) = tokio::sync::mpsc::channel(1);
         let service = VoteService::new(sender);
-        let vote = Vote { user_id: "user_id".to_string(), game_uuid: "game_uuid".to_string(), team_code: "team_code".to_string(), is_home_winner: true };
-        let vote2 = Vote { user_id: "user_id2".to_string(), game_uuid: "game_uuid".to_string(), team_code: "team_code".to_string(), is_home_winner: true };
+        let vote = Vote { user_id: "user_id".to_string(), game_uuid: "game_uuid".to_string(), team_code: "team_code".to_string(), is_home_winner: true, created: None };
+        let vote2 = Vote { user_id: "user_id2".to_string(), game_uuid: "game_uuid".to_string(), team_code: "team_code".to_string(), is_home_winner: true, created: None };
 
         // When
         service.write().await.vote(vote.clone()).await;
@@ -114,4 +258,53 @@ mod tests{
         assert!(votes.iter().any(|e| { e.game_uuid == vote.game_uuid && e.user_id == vote.user_id }));
         assert!(votes.iter().any(|e| { e.game_uuid == vote2.game_uuid && e.user_id == vote2.user_id }));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn anomalies() {
+        // Given
+        let start = Utc::now();
+        let vote = |user_id: &str, game_uuid: String, created: DateTime<Utc>| Vote {
+            user_id: user_id.to_string(), game_uuid, team_code: "LHF".to_string(), is_home_winner: true, created: Some(created),
+        };
+        let mut votes = vec![vote("random_id", "game_0".to_string(), start)];
+        for i in 0..40 {
+            votes.push(vote("bot", format!("game_{i}"), start + Duration::seconds(i)));
+            votes.push(vote("fan", format!("game_{i}"), start + Duration::minutes(i * 10)));
+        }
+
+        // When
+        let anomalies = VoteService::get_anomalies(&votes, |user_id| user_id != "random_id");
+
+        // Then
+        assert_eq!(anomalies.unknown_users, vec!["random_id"]);
+        assert_eq!(anomalies.burst_users, vec!["bot"]);
+        assert_eq!(anomalies.nr_votes, 41);
+    }
+
+    #[tokio::test]
+    async fn rate_limit() {
+        before();
+        let (sender, _) = tokio::sync::mpsc::channel(1);
+        let service = VoteService::new(sender);
+        let mut vs = service.write().await;
+        for _ in 0..MAX_VOTES_PER_USER {
+            assert!(!vs.is_rate_limited("user_id", Some("10.0.0.1")));
+        }
+        assert!(vs.is_rate_limited("user_id", Some("10.0.0.1")));
+        assert!(!vs.is_rate_limited("user_id2", Some("10.0.0.1")));
+    }
+
+    #[test]
+    fn rate_limit_sweep() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2);
+        for i in 0..100 {
+            assert!(!limiter.is_limited(&format!("10.0.0.{i}"), start));
+        }
+        assert_eq!(limiter.hits.len(), 100);
+
+        let later = start + RATE_LIMIT_WINDOW + std::time::Duration::from_secs(1);
+        assert!(!limiter.is_limited("10.0.1.1", later));
+        assert_eq!(limiter.hits.len(), 1);
+    }
+}
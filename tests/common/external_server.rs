@@ -4,7 +4,7 @@ use async_stream::try_stream;
 use axum::{Router, extract::{Path, State, Query}, response::{IntoResponse, Sse, sse::{KeepAlive, Event}}, Json, body::StreamBody, routing::{get, post}};
 use reqwest::StatusCode;
 use serde::Deserialize;
-use shl_server_rs::{models_external::{season::{SeasonGame, SeasonRsp}, event::SseEvent}, models::{Season, GameType, League}};
+use shl_server_rs::{models_external::{season::{SeasonGame, SeasonRsp}, event::SseEvent}, models::{Season, GameType, League, SeasonRegistry}};
 use tokio::{sync::{RwLock, broadcast::Sender}, task::JoinHandle};
 use tokio_util::io::ReaderStream;
 
@@ -264,16 +264,8 @@ impl ExternalServer {
 
 
     fn parse_season_uuid(str: &str) -> Result<Season, ()> {
-        match str {
-            "qcz-3NvSZ2Cmh" => Ok(Season::Season2023),
-            "qbN-XMFfjGVt" => Ok(Season::Season2022),
-            "qZl-8qa6OaFXf" => Ok(Season::Season2021),
-            "qY7-AdVh5z1XJ" => Ok(Season::Season2020),
-            "qWX-334j11U5o1" => Ok(Season::Season2019),
-            "qUv-YXiuQN45" => Ok(Season::Season2018),
-            _ => Err(()),
-        }
-    } 
+        SeasonRegistry::from_uuid(str).ok_or(())
+    }
 }
 
 async fn remove_first<T>(vec: Arc<RwLock<Vec<T>>>) -> Option<T> {
@@ -12,8 +12,8 @@ pub struct ShlServer {
 
 impl Drop for ShlServer {
     fn drop(&mut self) {
-        if self.child_process.is_some() {
-            self.child_process.as_mut().unwrap().kill()
+        if let Some(child_process) = self.child_process.as_mut() {
+            child_process.kill()
                 .expect("Should kill");
         }
     }
@@ -3,7 +3,7 @@ use chrono::Utc;
 use common::models_apn::ApnBody;
 use reqwest::StatusCode;
 use serde::Deserialize;
-use shl_server_rs::{models_api::{standings::Standings, game_details::ApiGameDetails, game::ApiGame, user::AddUser, report::GameStatus, live_activity::StartLiveActivity, vote::{VoteBody, VotePerGame, ApiVotePerGame}}, models_external::{event::{SseEvent, GameReport, LiveEvent, PeriodType, EventType, ShotType, LiveEventTeam, EventTeam, EventPlayer, SseGameTime, LiveState, LiveStateEvent}, season::{SeasonGame, GameTeamInfo, SeriesInfo, TeamNames}}, models::{Season, StringOrNum, GameType}};
+use shl_server_rs::{models_api::{standings::Standings, game_details::ApiGameDetails, game::ApiGame, user::AddUser, report::GameStatus, live_activity::StartLiveActivity, vote::{VoteBody, VotePerGame, ApiVotePerGame}}, models_external::{event::{SseEvent, GameReport, LiveEvent, PeriodType, EventType, ShotType, LiveEventTeam, EventTeam, EventPlayer, SseGameTime, LiveState, LiveStateEvent}, season::{SeasonGame, GameTeamInfo, SeriesInfo, TeamNames}}, models::{Season, StringOrNum, GameType, League}};
 use std::{time::Instant, fs::File, io::BufReader};
 use tempdir::TempDir;
 use std::io::BufRead;
@@ -20,7 +20,7 @@ async fn test_vote_service() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut external_server = ExternalServer::new(8003);
     external_server.start().await;
-    external_server.add_game(Season::Season2023, GameType::Season, SeasonGame { 
+    external_server.add_game(Season(2023), GameType::Season, SeasonGame { 
         uuid: "game_uuid_1".to_string(), 
         homeTeamInfo: get_team_info("SAIK", 0), 
         awayTeamInfo: get_team_info("OHK", 0), 
@@ -35,7 +35,7 @@ async fn test_vote_service() -> Result<(), Box<dyn std::error::Error>> {
     let mut server = ShlServer::new(8004);
     server.start(path, &external_server.get_url());
 
-    let req = AddUser { id: "user_id_1".to_string(), teams: vec!["SAIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_1".to_string(), teams: vec!["SAIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     // When - make request without api-key
@@ -59,7 +59,7 @@ async fn test_vote_service() -> Result<(), Box<dyn std::error::Error>> {
     // When - add multiple votes
     for i in 0..=100 {
         let user_id = format!("user_id_SAIK_{i}");
-        let req = AddUser { id: user_id.clone(), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: user_id.clone(), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.add_user(&req).await?;
         let req = &VoteBody { game_uuid: "game_uuid_1".to_string(), user_id, team_code: "SAIK".to_string() };
         let res = server.vote(req, Some("API_KEY")).await?;
@@ -74,7 +74,7 @@ async fn test_vote_service() -> Result<(), Box<dyn std::error::Error>> {
     // When - add multiple votes
     for i in 0..=9 {
         let user_id = format!("user_id_OHK_{i}");
-        let req = AddUser { id: user_id.clone(), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_OHK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: user_id.clone(), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_OHK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.add_user(&req).await?;
         let req = &VoteBody { game_uuid: "game_uuid_1".to_string(), user_id, team_code: "OHK".to_string() };
         let res = server.vote(req, Some("API_KEY")).await?;
@@ -91,7 +91,7 @@ async fn test_vote_service() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(details.game.votes.unwrap().home_perc, 91);
     assert_eq!(details.game.votes.unwrap().away_perc, 9);
 
-    let season_games = server.get_api_games(Season::Season2023).await?;
+    let season_games = server.get_api_games(Season(2023)).await?;
     let season_game = season_games.iter().find(|e| e.game_uuid == "game_uuid_1").unwrap();
     assert_eq!(details.game.votes.unwrap().home_perc, season_game.votes.unwrap().home_perc);
     assert_eq!(details.game.votes.unwrap().away_perc, season_game.votes.unwrap().away_perc);
@@ -129,19 +129,19 @@ async fn test_process_full_game() -> Result<(), Box<dyn std::error::Error>> {
 
     // When - add users that should receive notifications
     for i in 0..100 {
-        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
     }
 
     // When - add users that should not receive notifications
     for i in 0..100 {
-        let req = AddUser { id: format!("user_id_LHF_{i}"), teams: vec!["LHF".to_string()], apn_token: Some(format!("apn_token_LHF_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_LHF_{i}"), teams: vec!["LHF".to_string()], apn_token: Some(format!("apn_token_LHF_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
     }
 
     // When - add users that should receive live activities
     for i in 0..10 {
-        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_live_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_live_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
         let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: "qcv-34ekyLqu8".to_string() };
         _ = server.start_live_acitivty(&start_req).await;
@@ -154,18 +154,18 @@ async fn test_process_full_game() -> Result<(), Box<dyn std::error::Error>> {
 
     {
         // Then - all games should be available
-        let games_rsp: Vec<ApiGame> = server.get_api_games(Season::Season2023).await?;
+        let games_rsp: Vec<ApiGame> = server.get_api_games(Season(2023)).await?;
         assert_eq!(games_rsp.len(), 1);
     }
 
     {
         // Then - standings should be available
-        let standings_rsp: Standings = server.get_api_standings(Season::Season2023).await?;
-        assert_eq!(standings_rsp.SHL.len(), 2);
-        assert_eq!(standings_rsp.SHL[0].team_code, "OHK");
-        assert_eq!(standings_rsp.SHL[0].points, 0);
-        assert_eq!(standings_rsp.SHL[1].team_code, "SAIK");
-        assert_eq!(standings_rsp.SHL[1].points, 0);
+        let standings_rsp: Standings = server.get_api_standings(Season(2023)).await?;
+        assert_eq!(standings_rsp.get(&League::SHL).len(), 2);
+        assert_eq!(standings_rsp.get(&League::SHL)[0].team_code, "OHK");
+        assert_eq!(standings_rsp.get(&League::SHL)[0].points, 0);
+        assert_eq!(standings_rsp.get(&League::SHL)[1].team_code, "SAIK");
+        assert_eq!(standings_rsp.get(&League::SHL)[1].points, 0);
     }
 
     {
@@ -231,7 +231,7 @@ async fn test_game_decoration() -> Result<(), Box<dyn std::error::Error>> {
     let mut server = ShlServer::new(8006);
     server.start(path, &external_server.get_url());
 
-    let req = AddUser { id: "user_id_SAIK_1".to_string(), teams: vec!["SAIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_1".to_string(), teams: vec!["SAIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     // When - without any report
@@ -239,7 +239,7 @@ async fn test_game_decoration() -> Result<(), Box<dyn std::error::Error>> {
     let game_uuid = "game_uuid_1";
     {
         // Then - game should be in status Coming
-        let rsp = server.get_api_games(Season::Season2023).await?;
+        let rsp = server.get_api_games(Season(2023)).await?;
         let game: &ApiGame = rsp.iter().find(|e| e.game_uuid == game_uuid).unwrap();
         assert_eq!(game.status, GameStatus::Coming);
     }
@@ -263,7 +263,7 @@ async fn test_game_decoration() -> Result<(), Box<dyn std::error::Error>> {
         let details = server.retry_until_game_reaches(game_uuid, &GameStatus::Period1, 1000).await;
         assert_eq!(details.game.gametime.unwrap(), "13:37");
         assert!(!details.game.played);
-        let rsp = server.get_api_games(Season::Season2023).await?;
+        let rsp = server.get_api_games(Season(2023)).await?;
         let game: &ApiGame = rsp.iter().find(|e| e.game_uuid == game_uuid).unwrap();
         assert_eq!(game.status, GameStatus::Period1);
         assert_eq!(game.home_team_result, 2);
@@ -282,7 +282,7 @@ async fn test_game_decoration() -> Result<(), Box<dyn std::error::Error>> {
         let details = server.retry_until_game_reaches(game_uuid, &GameStatus::Intermission, 1000).await;
         assert_eq!(details.game.gametime.unwrap(), "20:00");
         assert!(!details.game.played);
-        let rsp = server.get_api_games(Season::Season2023).await?;
+        let rsp = server.get_api_games(Season(2023)).await?;
         let game: &ApiGame = rsp.iter().find(|e| e.game_uuid == game_uuid).unwrap();
         assert_eq!(game.status, GameStatus::Intermission);
     }
@@ -295,7 +295,7 @@ async fn test_game_decoration() -> Result<(), Box<dyn std::error::Error>> {
         // Then - game should be in status Finished
         let details = server.retry_until_game_reaches(game_uuid, &GameStatus::Finished, 1000).await;
         assert!(details.game.played);
-        let rsp = server.get_api_games(Season::Season2023).await?;
+        let rsp = server.get_api_games(Season(2023)).await?;
         let game: &ApiGame = rsp.iter().find(|e| e.game_uuid == game_uuid).unwrap();
         assert_eq!(game.status, GameStatus::Finished);
         assert!(game.played);
@@ -349,13 +349,13 @@ async fn test_multiple_live_games() -> Result<(), Box<dyn std::error::Error>> {
 
     // When - add users that should receive notifications
     for i in 0..10 {
-        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
     }
 
     // When - add users that should receive notifications
     for i in 0..10 {
-        let req = AddUser { id: format!("user_id_LHF_{i}"), teams: vec!["LHF".to_string()], apn_token: Some(format!("apn_token_LHF_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_LHF_{i}"), teams: vec!["LHF".to_string()], apn_token: Some(format!("apn_token_LHF_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
     }
 
@@ -446,12 +446,12 @@ async fn test_live_events() -> Result<(), Box<dyn std::error::Error>> {
 
     // When - add users that should receive notifications
     for i in 0..10 {
-        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
     }
 
     for i in 0..10 {
-        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
         let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: "game_uuid_1".to_string() };
         _ = server.start_live_acitivty(&start_req).await;
@@ -471,7 +471,7 @@ async fn test_live_events() -> Result<(), Box<dyn std::error::Error>> {
     let all_notifications = external_server.api_state.read().await.notifications.clone();
     assert_eq!(all_notifications.len(), 10);
 
-    let not = all_notifications.get(0).unwrap();
+    let not = all_notifications.first().unwrap();
     assert_eq!(not.1.aps.alert.as_ref().unwrap().title, "Nedsläpp");
 
     let all_live = external_server.api_state.read().await.nr_live_activities["apn_token_SAIK_0"];
@@ -597,7 +597,7 @@ async fn test_live_events_report() -> Result<(), Box<dyn std::error::Error>> {
 
     // When - add users that should receive notifications
     for i in 0..1 {
-        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None };
+        let req = AddUser { id: format!("user_id_SAIK_live_{i}"), teams: vec!["SAIK".to_string()], apn_token: Some(format!("apn_token_SAIK_{i}")), ios_version: None, app_version: None, vote_results: None };
         server.retry_add_user(&req).await;
         let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: "game_uuid_1".to_string() };
         _ = server.start_live_acitivty(&start_req).await;
@@ -781,11 +781,11 @@ async fn test_vlh_mif_2023_09_16() -> Result<(), Box<dyn std::error::Error>> {
     }).await;
 
     // notification user
-    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["VLH".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["VLH".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     // live activity user
-    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["VLH".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["VLH".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
     let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: game_uuid.clone() };
     _ = server.start_live_acitivty(&start_req).await;
@@ -843,11 +843,11 @@ async fn test_lhf_tik_2023_09_16() -> Result<(), Box<dyn std::error::Error>> {
     }).await;
 
     // notification user
-    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["TIK".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["TIK".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     // live activity user
-    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["TIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["TIK".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
     let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: game_uuid.clone() };
     _ = server.start_live_acitivty(&start_req).await;
@@ -874,7 +874,7 @@ async fn test_lhf_tik_2023_09_16() -> Result<(), Box<dyn std::error::Error>> {
     let expected_events = vec!["Nedsläpp", "Period 1", "Period 2", "Period 3", "LHF vann"];
     assert_live_activities(expected_events, &apn_state.live_activities);
 
-    let standings = server.get_api_standings(Season::Season2023).await?;
+    let standings = server.get_api_standings(Season(2023)).await?;
     assert_standing(&standings, "LHF", 1, 1, 3).await;
     assert_standing(&standings, "TIK", 2, 1, 0).await;
 
@@ -908,11 +908,11 @@ async fn test_mif_modo_2023_09_30() -> Result<(), Box<dyn std::error::Error>> {
     }).await;
 
     // notification user
-    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("apn_token_SAIK_0".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     // live activity user
-    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("apn_token_SAIK_1".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
     let start_req = StartLiveActivity { user_id: req.id, token: req.apn_token.unwrap(), game_uuid: game_uuid.clone() };
     _ = server.start_live_acitivty(&start_req).await;
@@ -927,7 +927,7 @@ async fn test_mif_modo_2023_09_30() -> Result<(), Box<dyn std::error::Error>> {
     assert!(!game_details.game.shootout);
     assert_eq!(game_details.events.len(), 121);
 
-    let standings = server.get_api_standings(Season::Season2023).await?;
+    let standings = server.get_api_standings(Season(2023)).await?;
     assert_standing(&standings, "MODO", 1, 1, 2).await;
     assert_standing(&standings, "MIF", 2, 1, 1).await;
 
@@ -961,11 +961,11 @@ async fn test_apn_error() -> Result<(), Box<dyn std::error::Error>> {
     }).await;
 
     // notification user
-    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("unregistered_token".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_0".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("unregistered_token".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
-    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("baddevice_token".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_1".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("baddevice_token".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
-    let req = AddUser { id: "user_id_SAIK_live_2".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("ok_token".to_string()), ios_version: None, app_version: None };
+    let req = AddUser { id: "user_id_SAIK_live_2".to_string(), teams: vec!["MODO".to_string()], apn_token: Some("ok_token".to_string()), ios_version: None, app_version: None, vote_results: None };
     server.retry_add_user(&req).await;
 
     external_server.api_state.write().await.apn_response.insert("unregistered_token".to_string(), (StatusCode::BAD_REQUEST, "Unregistered".to_string()));
@@ -1055,7 +1055,7 @@ fn assert_live_activities(expc_titles: Vec<&str>, live_activities: &[(String, Ap
 }
 
 async fn assert_standing(standings: &Standings, team: &str, rank: u8, gp: u16, points: u16) {
-    let entry = standings.SHL.iter().find(|e| e.team_code == team).unwrap();
+    let entry = standings.get(&League::SHL).iter().find(|e| e.team_code == team).unwrap();
     assert_eq!(entry.rank, rank);
     assert_eq!(entry.gp, gp);
     assert_eq!(entry.points, points);